@@ -33,9 +33,22 @@
 //!
 //! # Known Uses
 //! Creation of UI controls.
+//!
+//! # Example
+//! ```
+//! use patterns::abstract_factory::{Button, Factory, Linux, Window};
+//!
+//! fn paint_ui(factory: &dyn Factory) -> String {
+//!     let window = factory.create_window();
+//!     let button = factory.create_button();
+//!     format!("{} in a {:?} window", button.paint(), window.size())
+//! }
+//!
+//! assert_eq!(paint_ui(&Linux), "LinuxButton in a (400, 400) window");
+//! ```
 
 /// A trait defining a button.
-trait Button {
+pub trait Button {
     /// Returns the label on the button.
     ///
     /// # Returns
@@ -45,7 +58,7 @@ trait Button {
 
 
 /// A generic window trait.
-trait Window {
+pub trait Window {
     /// Returns the size of the window.
     ///
     /// # Returns
@@ -54,24 +67,24 @@ trait Window {
 }
 
 /// A trait defining an abstract factory.
-trait Factory {
+pub trait Factory {
     /// Creates a button.
     ///
     /// # Returns
-    /// A button trait object (Box<Button>).
-    fn create_button(&self) -> Box<Button>;
+    /// A button trait object (Box<dyn Button>).
+    fn create_button(&self) -> Box<dyn Button>;
 
     /// Creates a window.
     ///
     /// # Returns
-    /// A window trait object (Box<Window>).
-    fn create_window(&self) -> Box<Window>;
+    /// A window trait object (Box<dyn Window>).
+    fn create_window(&self) -> Box<dyn Window>;
 }
 
 /// A linux button.
-struct LinuxButton;
+pub struct LinuxButton;
 /// A linux window
-struct LinuxWindow;
+pub struct LinuxWindow;
 
 impl Button for LinuxButton {
     fn paint(&self) -> &str {
@@ -86,9 +99,9 @@ impl Window for LinuxWindow {
 }
 
 /// A OSX button.
-struct OSXButton;
+pub struct OSXButton;
 /// A OSX window.
-struct OSXWindow;
+pub struct OSXWindow;
 
 impl Button for OSXButton {
     fn paint(&self) -> &str {
@@ -103,26 +116,27 @@ impl Window for OSXWindow {
 }
 
 /// A linux system.
-struct Linux;
+pub struct Linux;
 /// A OSX system.
-struct OSX;
+#[allow(clippy::upper_case_acronyms)]
+pub struct OSX;
 
 impl Factory for Linux {
-    fn create_button(&self) -> Box<Button> {
+    fn create_button(&self) -> Box<dyn Button> {
         Box::new(LinuxButton {})
     }
 
-    fn create_window(&self) -> Box<Window> {
+    fn create_window(&self) -> Box<dyn Window> {
         Box::new(LinuxWindow {})
     }
 }
 
 impl Factory for OSX {
-    fn create_button(&self) -> Box<Button> {
+    fn create_button(&self) -> Box<dyn Button> {
         Box::new(OSXButton {})
     }
 
-    fn create_window(&self) -> Box<Window> {
+    fn create_window(&self) -> Box<dyn Window> {
         Box::new(OSXWindow {})
     }
 }
@@ -132,16 +146,22 @@ impl Factory for OSX {
 /// exposes the real type of the object to the client. This might however, not be a problem in some scenarios. In terms
 /// of performance, this is prefered, as no dynamic lookup is required on method calls on the objects created by the
 /// abstract factory.
-trait Factory2 {
+pub trait Factory2 {
+    /// The concrete button type built by the factory.
     type ButtonType: Button;
+    /// The concrete window type built by the factory.
     type WindowType: Window;
 
+    /// Creates a button.
     fn create_button(&self) -> Self::ButtonType;
+    /// Creates a window.
     fn create_window(&self) -> Self::WindowType;
 }
 
-struct DebianButton;
-struct DebianWindow;
+/// A debian button.
+pub struct DebianButton;
+/// A debian window.
+pub struct DebianWindow;
 
 impl Button for DebianButton {
     fn paint(&self) -> &str {
@@ -156,7 +176,8 @@ impl Window for DebianWindow {
 }
 
 
-struct Debian;
+/// A debian system, building its products through `Factory2`.
+pub struct Debian;
 
 impl Factory2 for Debian {
     type ButtonType = DebianButton;
@@ -32,35 +32,49 @@
 //!
 //! # Known Uses
 //! - InterViews uses it for some graphics objects.
+//!
+//! # Example
+//! ```
+//! use patterns::adapter::{Client, USBCharger};
+//!
+//! let charger = USBCharger::new();
+//! assert_eq!(Client::do_stuff_with_usb(&charger), "Is charging using a USB-C adapter");
+//! ```
 
 
 /// The target interface to implement for an object.
-trait TargetInterface {
+pub trait TargetInterface {
+    /// Recharges the object and describes how it was done.
     fn recharge(&self) -> String;
 }
 
 /// Client requiring the target interface to be used.
-struct Client;
+pub struct Client;
 impl Client {
-    fn do_stuff_with_usb<T>(obj: &T) -> String where T: TargetInterface {
+    /// Uses any object implementing the target interface.
+    pub fn do_stuff_with_usb<T>(obj: &T) -> String where T: TargetInterface {
         obj.recharge()
     }
 }
 
 /// Object implementing the functionality of the target interface but not adhering to the interface.
-struct IPhone;
+#[derive(Default)]
+pub struct IPhone;
 impl IPhone {
-    fn charge(&self) -> &str {
+    /// Charges the phone through its own (non-USB) interface.
+    pub fn charge(&self) -> &str {
         "Is charging"
     }
 }
 
 /// Requires the charging functionality of `Phone` but with an interface of `TargetInterface`
-struct USBCharger {
+#[derive(Default)]
+pub struct USBCharger {
     phone: IPhone,       // Uses aggregation strategy.
 }
 impl USBCharger {
-    fn new() -> USBCharger {
+    /// Creates a charger holding a phone.
+    pub fn new() -> USBCharger {
         USBCharger {
             phone: IPhone,
         }
@@ -36,16 +36,36 @@
 //!
 //! # Known Uses:
 //! - `libg++` uses this pattern for some of its common data structures.
+//!
+//! # Example
+//! ```
+//! use patterns::bridge::{ColouredShape, DrawingAPI, Shape};
+//!
+//! struct DrawingGreen;
+//! impl DrawingAPI for DrawingGreen {
+//!     fn draw_circle(&self) -> &str {
+//!         "green circle"
+//!     }
+//!     fn draw_rectangle(&self) -> &str {
+//!         "green rectangle"
+//!     }
+//! }
+//!
+//! let shape = ColouredShape::new(DrawingGreen);
+//! assert_eq!(shape.draw(), "Shape drawing a green circle and a green rectangle");
+//! ```
 
 
 /// The API implemented by the implementation
-trait DrawingAPI {
+pub trait DrawingAPI {
+    /// Primitive operation drawing a circle.
     fn draw_circle(&self) -> &str;
+    /// Primitive operation drawing a rectangle.
     fn draw_rectangle(&self) -> &str;
 }
 
 /// A potential implementation of the `DrawingAPI`
-struct DrawingRed;
+pub struct DrawingRed;
 impl DrawingAPI for DrawingRed {
     fn draw_circle(&self) -> &str {
         "red circle"
@@ -56,7 +76,7 @@ impl DrawingAPI for DrawingRed {
 }
 
 /// Another potential implementation of the `DrawingAPI`
-struct DrawingBlue;
+pub struct DrawingBlue;
 impl DrawingAPI for DrawingBlue {
     fn draw_circle(&self) -> &str {
         "blue circle"
@@ -67,17 +87,21 @@ impl DrawingAPI for DrawingBlue {
 }
 
 /// The abstraction
-trait Shape<T> where T: DrawingAPI {
+pub trait Shape<T> where T: DrawingAPI {
+    /// Creates the abstraction on top of a given implementation.
     fn new(api: T) -> Self;
+    /// Draws a circle using the implementation.
     fn draw_circle(&self) -> &str;
+    /// Draws a rectangle using the implementation.
     fn draw_rectangle(&self) -> &str;
+    /// Higher level operation built from the primitives only.
     fn draw(&self) -> String {
         format!("Shape drawing a {} and a {}", self.draw_circle(), self.draw_rectangle())
     }
 }
 
 /// A concrete object using the abstraction
-struct ColouredShape<T> where T: DrawingAPI {
+pub struct ColouredShape<T> where T: DrawingAPI {
     api: T,
 }
 
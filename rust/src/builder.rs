@@ -7,7 +7,7 @@
 //!
 //! # Participants
 //! - `Builder`: the builder, it specifies n bastract interface for creating parts of a product object.
-//! - `CarBuilder`: a concrete builder implementing the `` interface. It defines and keeps track of the representation
+//! - `CarBuilder`: a concrete builder implementing the `Builder` interface. It defines and keeps track of the representation
 //!   it creates. It also provides an interface for retrieving the product it creates.
 //! - `CarBuilderDirector`: the director, responsible for the construction of the object using the builder interface.
 //! - `Car`: the product. It represents the complex object under construction. A concrete builder builds the product's
@@ -43,12 +43,23 @@
 //!
 //! # Known Uses
 //! - Text converters
+//!
+//! # Example
+//! ```
+//! use patterns::builder::{Builder, CarBuilder};
+//!
+//! let mut builder = CarBuilder::new();
+//! builder.set_colour("blue".to_string());
+//! builder.set_wheels(3);
+//! builder.set_seats(2);
+//! assert_eq!(builder.build().description(), "This is a blue car with 3 wheels and 2 seats.");
+//! ```
 
 
 
 /// A car
 #[derive(Clone)]
-struct Car {
+pub struct Car {
     /// Number of wheels the car has
     pub wheels: u8,
     /// Number of seats in the car
@@ -58,7 +69,8 @@ struct Car {
 }
 
 impl Car {
-    fn new() -> Self {
+    /// Creates a black car without any wheels or seats.
+    pub fn new() -> Self {
         Car {
             wheels: 0,
             seats: 0,
@@ -67,41 +79,44 @@ impl Car {
     }
 
     /// Returns a description of the car
-    fn description(&self) -> String {
+    pub fn description(&self) -> String {
         format!("This is a {} car with {} wheels and {} seats.", self.colour, self.wheels, self.seats)
     }
 }
 
-trait Builder {
+impl Default for Car {
+    fn default() -> Self {
+        Car::new()
+    }
+}
+
+/// The builder interface.
+pub trait Builder {
+    /// The complex object being built.
     type Product;
 
-    /// Set the wheel count of the car. Note that this is a blank method in order to allow concrete builders to only
-    /// implement the methods they require to build the complex object.
-    #[allow(unused_variables)]
-    fn set_wheels(&mut self, num: u8) {
-        unimplemented!();
-    }
+    /// Set the wheel count of the car.
+    fn set_wheels(&mut self, num: u8);
 
-    #[allow(unused_variables)]
-    fn set_seats(&mut self, num: u8) {
-        unimplemented!();
-    }
+    /// Set the seat count of the car.
+    fn set_seats(&mut self, num: u8);
 
-    #[allow(unused_variables)]
-    fn set_colour(&mut self, colour: String) {
-        unimplemented!();
-    }
+    /// Set the colour of the car.
+    fn set_colour(&mut self, colour: String);
 
-    /// This method is required for each builder.
+    /// Returns the product built so far.
     fn build(&self) -> Self::Product;
 }
 
-struct CarBuilder {
+/// A concrete builder building `Car`s.
+#[derive(Default)]
+pub struct CarBuilder {
     car: Car,
 }
 
 impl CarBuilder {
-    fn new() -> Self {
+    /// Creates a builder starting from a default car.
+    pub fn new() -> Self {
         CarBuilder {
             car: Car::new(),
         }
@@ -128,18 +143,22 @@ impl Builder for CarBuilder {
     }
 }
 
-struct CarBuilderDirector {
+/// The director constructing a red family car.
+#[derive(Default)]
+pub struct CarBuilderDirector {
     builder: CarBuilder,
 }
 
 impl CarBuilderDirector {
-    fn new() -> Self {
+    /// Creates a director with a fresh builder.
+    pub fn new() -> Self {
         CarBuilderDirector {
             builder: CarBuilder::new(),
         }
     }
 
-    fn construct(&mut self) -> Car {
+    /// Runs the construction process and returns the product.
+    pub fn construct(&mut self) -> Car {
         self.builder.set_colour("red".to_string());
         self.builder.set_wheels(4);
         self.builder.set_seats(5);
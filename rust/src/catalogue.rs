@@ -0,0 +1,318 @@
+//! Machine-readable catalogue of the patterns implemented in this crate.
+//!
+//! The catalogue is built from the module documentation of every pattern. Each module starts with the same structured
+//! sections (Theory, Participants, Modifications and Strategies, Attention and Known Uses) which are parsed into a
+//! `Pattern` record. The source of the modules is embedded at compile time, hence the catalogue always matches the
+//! documentation of the crate it is shipped with.
+//!
+//! # Example
+//! ```
+//! use patterns::catalogue::{Catalogue, Category};
+//!
+//! let catalogue = Catalogue::new();
+//! let chain = catalogue.get("chain_of_responsibility").unwrap();
+//! assert_eq!(chain.category, Category::Behavioural);
+//! assert_eq!(chain.participants[0].types, vec!["PurchasePower"]);
+//! ```
+
+use std::fmt;
+
+/// The purpose of a pattern, as classified in _Design Patterns: Elements of Reusable Object-Oriented software_.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Category {
+    /// Patterns abstracting the instantiation process.
+    Creational,
+    /// Patterns concerned with how classes and objects are composed to form larger structures.
+    Structural,
+    /// Patterns concerned with algorithms and the assignment of responsibilities between objects.
+    Behavioural,
+}
+
+impl Category {
+    /// The name of the category in lowercase.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Category::Creational    => "creational",
+            Category::Structural    => "structural",
+            Category::Behavioural   => "behavioural",
+        }
+    }
+
+    /// Parses a category from its name. Both British and American spellings of "behavioural" are accepted.
+    pub fn from_name(name: &str) -> Option<Category> {
+        match name.to_lowercase().as_str() {
+            "creational"                    => Some(Category::Creational),
+            "structural"                    => Some(Category::Structural),
+            "behavioural" | "behavioral"    => Some(Category::Behavioural),
+            _                               => None,
+        }
+    }
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+/// A participant of a pattern.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Participant {
+    /// The Rust types playing this role. This is empty if the role is not played by a type of the module (for instance
+    /// the client of the pattern).
+    pub types: Vec<String>,
+    /// The role the participant plays in the pattern.
+    pub description: String,
+}
+
+/// The documentation of a single pattern.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pattern {
+    /// The name of the module implementing the pattern.
+    pub name: &'static str,
+    /// The human readable name of the pattern.
+    pub title: &'static str,
+    /// The category of the pattern.
+    pub category: Category,
+    /// The _Theory_ section.
+    pub theory: String,
+    /// The items of the _Participants_ section.
+    pub participants: Vec<Participant>,
+    /// The _Modifications and Strategies_ section, if any.
+    pub strategies: Option<String>,
+    /// The _Attention_ section, if any.
+    pub attention: Option<String>,
+    /// The items of the _Known Uses_ section.
+    pub known_uses: Vec<String>,
+    /// Any other section of the documentation (such as _Note_), as heading and text pairs.
+    pub notes: Vec<(String, String)>,
+}
+
+/// The modules documented in the catalogue: name, title, category and source.
+const SOURCES: &[(&str, &str, Category, &str)] = &[
+    ("factory_method", "Factory Method", Category::Creational, include_str!("factory_method.rs")),
+    ("abstract_factory", "Abstract Factory", Category::Creational, include_str!("abstract_factory.rs")),
+    ("prototype", "Prototype", Category::Creational, include_str!("prototype.rs")),
+    ("singleton", "Singleton", Category::Creational, include_str!("singleton.rs")),
+    ("builder", "Builder", Category::Creational, include_str!("builder.rs")),
+    ("composite", "Composite", Category::Structural, include_str!("composite.rs")),
+    ("decorator", "Decorator", Category::Structural, include_str!("decorator.rs")),
+    ("proxy", "Proxy", Category::Structural, include_str!("proxy.rs")),
+    ("adapter", "Adapter", Category::Structural, include_str!("adapter.rs")),
+    ("bridge", "Bridge", Category::Structural, include_str!("bridge.rs")),
+    ("facade", "Façade", Category::Structural, include_str!("facade.rs")),
+    ("flyweight", "Flyweight", Category::Structural, include_str!("flyweight.rs")),
+    ("command", "Command", Category::Behavioural, include_str!("command/mod.rs")),
+    ("observer", "Observer", Category::Behavioural, include_str!("observer/mod.rs")),
+    ("state", "State", Category::Behavioural, include_str!("state.rs")),
+    ("strategy", "Strategy", Category::Behavioural, include_str!("strategy.rs")),
+    ("template_method", "Template Method", Category::Behavioural, include_str!("template_method.rs")),
+    ("chain_of_responsibility", "Chain of Responsibility", Category::Behavioural,
+     include_str!("chain_of_responsibility/mod.rs")),
+    ("iterator", "Iterator", Category::Behavioural, include_str!("iterator.rs")),
+    ("mediator", "Mediator", Category::Behavioural, include_str!("mediator.rs")),
+];
+
+/// The catalogue of all patterns.
+pub struct Catalogue {
+    patterns: Vec<Pattern>,
+}
+
+impl Catalogue {
+    /// Builds the catalogue from the documentation of every pattern module.
+    pub fn new() -> Catalogue {
+        Catalogue {
+            patterns: SOURCES.iter().map(|&(name, title, category, source)| {
+                Pattern::parse(name, title, category, source)
+            }).collect(),
+        }
+    }
+
+    /// Returns all patterns, grouped by category.
+    pub fn patterns(&self) -> &[Pattern] {
+        &self.patterns
+    }
+
+    /// Returns the pattern implemented in the module with the given name.
+    pub fn get(&self, name: &str) -> Option<&Pattern> {
+        self.patterns.iter().find(|pattern| pattern.name == name)
+    }
+
+    /// Returns the patterns of a given category.
+    pub fn by_category(&self, category: Category) -> Vec<&Pattern> {
+        self.patterns.iter().filter(|pattern| pattern.category == category).collect()
+    }
+}
+
+impl Default for Catalogue {
+    fn default() -> Self {
+        Catalogue::new()
+    }
+}
+
+impl Pattern {
+    /// Parses the module documentation (the leading `//!` lines) of a pattern module.
+    fn parse(name: &'static str, title: &'static str, category: Category, source: &str) -> Pattern {
+        let mut pattern = Pattern {
+            name,
+            title,
+            category,
+            theory: String::new(),
+            participants: Vec::new(),
+            strategies: None,
+            attention: None,
+            known_uses: Vec::new(),
+            notes: Vec::new(),
+        };
+        for (heading, lines) in sections(source) {
+            match heading.as_str() {
+                "Theory"                        => pattern.theory = text(&lines),
+                "Participants"                  => pattern.participants = items(&lines).iter()
+                    .map(|item| Participant::parse(item))
+                    .collect(),
+                "Modifications and Strategies"  => pattern.strategies = Some(text(&lines)),
+                "Attention"                     => pattern.attention = Some(text(&lines)),
+                "Known Uses"                    => pattern.known_uses = items(&lines),
+                // Examples are doc-tests, they are not part of the description of the pattern.
+                "Example"                       => (),
+                _                               => pattern.notes.push((heading, text(&lines))),
+            }
+        }
+        pattern
+    }
+}
+
+impl Participant {
+    /// Parses a participant list item such as "`A`, `B`: description".
+    fn parse(item: &str) -> Participant {
+        if item.starts_with('`') {
+            if let Some(idx) = item.find(": ") {
+                let head = &item[..idx];
+                return Participant {
+                    types: head.split('`').skip(1).step_by(2).map(String::from).collect(),
+                    description: item[idx + 2..].to_string(),
+                };
+            }
+        }
+        Participant {
+            types: Vec::new(),
+            description: item.to_string(),
+        }
+    }
+}
+
+/// Splits the module documentation into top level sections.
+fn sections(source: &str) -> Vec<(String, Vec<&str>)> {
+    let mut result: Vec<(String, Vec<&str>)> = Vec::new();
+    let docs = source.lines().take_while(|line| line.starts_with("//!"));
+    for line in docs.map(|line| line.trim_start_matches("//!").strip_prefix(' ').unwrap_or("")) {
+        if let Some(heading) = line.strip_prefix("# ") {
+            result.push((heading.trim_end_matches(':').to_string(), Vec::new()));
+        } else if let Some(&mut (_, ref mut lines)) = result.last_mut() {
+            lines.push(line);
+        }
+    }
+    result
+}
+
+/// Unwraps the hard-wrapped lines of a section into paragraphs separated by blank lines. List items and sub-headings
+/// are kept on their own lines.
+fn text(lines: &[&str]) -> String {
+    let mut result = String::new();
+    let mut paragraph_start = true;
+    for line in lines {
+        if line.trim().is_empty() {
+            paragraph_start = true;
+            continue;
+        }
+        let new_line = line.starts_with("- ") || line.starts_with('#') || starts_numbered(line);
+        if !result.is_empty() {
+            result.push_str(if paragraph_start { "\n\n" } else if new_line { "\n" } else { " " });
+        }
+        result.push_str(line.trim());
+        paragraph_start = line.starts_with('#');
+    }
+    result
+}
+
+/// Parses the items of a markdown list. Sections without a list are returned as a single item.
+fn items(lines: &[&str]) -> Vec<String> {
+    let mut result: Vec<String> = Vec::new();
+    for line in lines.iter().filter(|line| !line.trim().is_empty()) {
+        match (line.strip_prefix("- "), result.last_mut()) {
+            (Some(item), _)     => result.push(item.trim().to_string()),
+            (None, Some(last))  => {
+                last.push(' ');
+                last.push_str(line.trim());
+            },
+            (None, None)        => result.push(line.trim().to_string()),
+        }
+    }
+    result
+}
+
+/// Whether a line starts an item of a numbered list.
+fn starts_numbered(line: &str) -> bool {
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    digits > 0 && line[digits..].starts_with(". ")
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalogue() {
+        let catalogue = Catalogue::new();
+        assert_eq!(catalogue.patterns().len(), 20);
+        assert_eq!(catalogue.by_category(Category::Creational).len(), 5);
+        assert_eq!(catalogue.by_category(Category::Structural).len(), 7);
+        assert_eq!(catalogue.by_category(Category::Behavioural).len(), 8);
+        assert_eq!(Category::from_name("Behavioral"), Some(Category::Behavioural));
+
+        let command = catalogue.get("command").unwrap();
+        assert_eq!(command.title, "Command");
+        assert!(command.theory.starts_with("This is a behavioural design pattern. It is used to encapsulate"));
+        assert_eq!(command.participants[1].types, vec!["LightOnCommand", "LightOffCommand", "ToggleCommand",
+                                                       "DimCommand"]);
+        assert_eq!(command.known_uses, vec!["Wizard", "Undo-redo", "Log-files and re-execution",
+                                            "Transactional Operations"]);
+        assert!(command.attention.is_none());
+
+        let abstract_factory = catalogue.get("abstract_factory").unwrap();
+        let client = abstract_factory.participants.last().unwrap();
+        assert!(client.types.is_empty());
+        assert!(client.description.starts_with("The client."));
+        assert_eq!(abstract_factory.known_uses, vec!["Creation of UI controls."]);
+
+        let builder = catalogue.get("builder").unwrap();
+        let attention = builder.attention.as_ref().unwrap();
+        assert!(attention.contains("alternatives:\n1. Return a reference to the object."));
+
+        let state = catalogue.get("state").unwrap();
+        assert_eq!(state.notes, vec![("Note".to_string(),
+                                      "The following code is taken from the official Rust book.".to_string())]);
+
+        for pattern in catalogue.patterns() {
+            assert!(!pattern.theory.is_empty(), "{} has no theory", pattern.name);
+            assert!(!pattern.participants.is_empty(), "{} has no participants", pattern.name);
+        }
+    }
+
+    #[test]
+    fn test_catalogue_participants_exist() {
+        // Participants that are not declared in their module: standard library traits, and roles without code.
+        let external = ["Ord", "Iterator", "IntoIterator", "Client", "Prototype", "ConcretePrototype"];
+        for &(name, _, _, source) in SOURCES {
+            let pattern = Catalogue::new().get(name).cloned().unwrap();
+            for participant in &pattern.participants {
+                for ty in participant.types.iter().filter(|ty| !external.contains(&ty.as_str())) {
+                    let declared = ["struct", "trait", "enum", "type"].iter()
+                        .any(|kind| source.contains(&format!("pub {} {}", kind, ty)));
+                    assert!(declared, "participant {} of {} is not declared", ty, name);
+                }
+            }
+        }
+    }
+}
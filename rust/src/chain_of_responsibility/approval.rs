@@ -0,0 +1,360 @@
+//! Multi-signature approvals.
+//!
+//! In the classic chain, exactly one handler takes care of a request. Purchase policies however often require
+//! co-signatures above some amount, for instance a vice-president and the president for anything over $30000. An
+//! `ApprovalPolicy` passes a request along a chain of `Approver`s and applies `CoSignRule`s listing the roles that must
+//! sign requests above some amount, in addition to the approver responsible for the amount.
+//!
+//! Every approver the request passes through is recorded in an audit trail, along with its decision and the reason for
+//! it. The final decision is returned as an `ApprovalRecord`.
+
+use std::fmt;
+
+use super::{Employee, PurchasePower, PurchaseRequest};
+
+/// The decision of a single approver.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Decision {
+    /// The approver is responsible for the amount and approved the request.
+    Approved,
+    /// The approver signed the request as a required co-signer.
+    CoSigned,
+    /// The approver cannot approve the amount and passed the request on.
+    Escalated,
+    /// The approver was not needed and passed the request on.
+    Skipped,
+    /// The approver refused the request.
+    Refused,
+}
+
+impl fmt::Display for Decision {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(match *self {
+            Decision::Approved  => "approved",
+            Decision::CoSigned  => "co-signed",
+            Decision::Escalated => "escalated",
+            Decision::Skipped   => "skipped",
+            Decision::Refused   => "refused",
+        })
+    }
+}
+
+/// The answer of an approver to a request.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Review {
+    /// The approver agrees, for the given reason.
+    Accept(String),
+    /// The approver does not agree, for the given reason.
+    Decline(String),
+}
+
+/// A processing object taking part in multi-signature approvals.
+pub trait Approver: Send + Sync {
+    /// The role of the approver, as referred to by co-signature rules.
+    fn role(&self) -> &str;
+
+    /// Reviews a request the approver might be responsible for. Declining passes the request on to the next approver.
+    fn review(&self, request: &PurchaseRequest) -> Review;
+
+    /// Reviews a request the approver must co-sign. Declining refuses the request altogether. By default, co-signers
+    /// sign every request they are presented.
+    fn cosign(&self, request: &PurchaseRequest) -> Review {
+        Review::Accept(format!("co-signs ${} for {}", request.get_amount(), request.get_purpose()))
+    }
+}
+
+impl Approver for Employee {
+    fn role(&self) -> &str {
+        self.get_role()
+    }
+
+    fn review(&self, request: &PurchaseRequest) -> Review {
+        if request.get_amount() < self.get_allowable() {
+            Review::Accept(format!("${} is below the limit of ${}", request.get_amount(), self.get_allowable()))
+        } else {
+            Review::Decline(format!("${} exceeds the limit of ${}", request.get_amount(), self.get_allowable()))
+        }
+    }
+}
+
+/// Requires some roles to sign every request above an amount.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CoSignRule {
+    above: u32,
+    signers: Vec<String>,
+}
+
+impl CoSignRule {
+    /// Creates a rule requiring all `signers` to sign requests for more than `above`.
+    pub fn new(above: u32, signers: &[&str]) -> CoSignRule {
+        CoSignRule {
+            above,
+            signers: signers.iter().map(|signer| signer.to_string()).collect(),
+        }
+    }
+
+    /// Whether the rule applies to the request.
+    pub fn applies_to(&self, request: &PurchaseRequest) -> bool {
+        request.get_amount() > self.above
+    }
+}
+
+/// An entry of the audit trail.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuditEntry {
+    /// The role of the approver.
+    pub approver: String,
+    /// Its decision.
+    pub decision: Decision,
+    /// The reason for the decision.
+    pub reason: String,
+}
+
+/// The final decision on a request.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Status {
+    /// The request was approved by a responsible approver and signed by all required co-signers.
+    Approved,
+    /// An approver refused the request.
+    Refused {
+        /// The role of the approver.
+        by: String,
+        /// The reason of the refusal.
+        reason: String,
+    },
+    /// No approver is responsible for the amount.
+    Unapproved,
+    /// Some required co-signers are not part of the chain.
+    MissingSignatures(Vec<String>),
+}
+
+/// The structured result of processing a request.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ApprovalRecord {
+    /// The request.
+    pub request: PurchaseRequest,
+    /// The final decision.
+    pub status: Status,
+    /// The roles that approved or co-signed the request, in order.
+    pub signatures: Vec<String>,
+    /// Every approver the request passed through.
+    pub trail: Vec<AuditEntry>,
+}
+
+impl ApprovalRecord {
+    /// Whether the request was approved.
+    pub fn is_approved(&self) -> bool {
+        self.status == Status::Approved
+    }
+}
+
+/// A chain of approvers along with the co-signature rules applying to it.
+pub struct ApprovalPolicy {
+    approvers: Vec<Box<dyn Approver>>,
+    rules: Vec<CoSignRule>,
+}
+
+impl ApprovalPolicy {
+    /// Starts building a policy.
+    pub fn builder() -> ApprovalPolicyBuilder {
+        ApprovalPolicyBuilder {
+            approvers: Vec::new(),
+            rules: Vec::new(),
+        }
+    }
+
+    /// The roles that must co-sign the request.
+    pub fn required_signers(&self, request: &PurchaseRequest) -> Vec<&str> {
+        let mut signers: Vec<&str> = Vec::new();
+        for rule in self.rules.iter().filter(|rule| rule.applies_to(request)) {
+            for signer in &rule.signers {
+                if !signers.contains(&signer.as_str()) {
+                    signers.push(signer);
+                }
+            }
+        }
+        signers
+    }
+
+    /// Passes the request along the approvers until it is approved and signed by all required co-signers, or refused.
+    pub fn process(&self, request: PurchaseRequest) -> ApprovalRecord {
+        let mut pending = self.required_signers(&request);
+        let mut approved = false;
+        let mut signatures = Vec::new();
+        let mut trail = Vec::new();
+        let mut status = None;
+
+        for approver in &self.approvers {
+            if approved && pending.is_empty() {
+                break;
+            }
+            let role = approver.role();
+            let required = pending.contains(&role);
+            let (decision, reason) = match (approved, approver.review(&request)) {
+                (false, Review::Accept(reason))     => (Decision::Approved, reason),
+                (false, Review::Decline(_)) if required => cosign(approver.as_ref(), &request),
+                (false, Review::Decline(reason))    => (Decision::Escalated, reason),
+                (true, _) if required               => cosign(approver.as_ref(), &request),
+                (true, _)                           => (Decision::Skipped, "no signature required".to_string()),
+            };
+
+            match decision {
+                Decision::Approved                      => approved = true,
+                Decision::Refused                       => status = Some(Status::Refused {
+                    by: role.to_string(),
+                    reason: reason.clone(),
+                }),
+                _                                       => (),
+            }
+            if decision == Decision::Approved || decision == Decision::CoSigned {
+                signatures.push(role.to_string());
+                pending.retain(|signer| *signer != role);
+            }
+            trail.push(AuditEntry { approver: role.to_string(), decision, reason });
+            if status.is_some() {
+                break;
+            }
+        }
+
+        let status = match status {
+            Some(status)                => status,
+            None if !approved           => Status::Unapproved,
+            None if !pending.is_empty() => Status::MissingSignatures(pending.iter().map(|s| s.to_string()).collect()),
+            None                        => Status::Approved,
+        };
+        ApprovalRecord { request, status, signatures, trail }
+    }
+}
+
+/// Asks an approver to co-sign a request.
+fn cosign(approver: &dyn Approver, request: &PurchaseRequest) -> (Decision, String) {
+    match approver.cosign(request) {
+        Review::Accept(reason)  => (Decision::CoSigned, reason),
+        Review::Decline(reason) => (Decision::Refused, reason),
+    }
+}
+
+/// Builds an `ApprovalPolicy`.
+pub struct ApprovalPolicyBuilder {
+    approvers: Vec<Box<dyn Approver>>,
+    rules: Vec<CoSignRule>,
+}
+
+impl ApprovalPolicyBuilder {
+    /// Appends an approver to the chain.
+    pub fn then<A>(mut self, approver: A) -> Self where A: Approver + 'static {
+        self.approvers.push(Box::new(approver));
+        self
+    }
+
+    /// Adds a co-signature rule.
+    pub fn rule(mut self, rule: CoSignRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Builds the policy.
+    pub fn build(self) -> ApprovalPolicy {
+        ApprovalPolicy {
+            approvers: self.approvers,
+            rules: self.rules,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A president who never signs for company cars.
+    struct President;
+    impl Approver for President {
+        fn role(&self) -> &str {
+            "president"
+        }
+
+        fn review(&self, request: &PurchaseRequest) -> Review {
+            if request.get_amount() < 100_000 {
+                self.cosign(request)
+            } else {
+                Review::Decline("beyond my authority".to_string())
+            }
+        }
+
+        fn cosign(&self, request: &PurchaseRequest) -> Review {
+            match request.get_purpose() {
+                "company car"   => Review::Decline("company cars are frozen".to_string()),
+                _               => Review::Accept("fine by me".to_string()),
+            }
+        }
+    }
+
+    fn policy() -> ApprovalPolicy {
+        ApprovalPolicy::builder()
+            .then(Employee::new("manager", 5000))
+            .then(Employee::new("director", 10_000))
+            .then(Employee::new("vice-president", 40_000))
+            .then(President)
+            .rule(CoSignRule::new(30_000, &["vice-president", "president"]))
+            .build()
+    }
+
+    fn decisions(record: &ApprovalRecord) -> Vec<(&str, Decision)> {
+        record.trail.iter().map(|entry| (entry.approver.as_str(), entry.decision)).collect()
+    }
+
+    #[test]
+    fn test_approval_policy() {
+        let record = policy().process(PurchaseRequest::new(7000, "printer"));
+        assert!(record.is_approved());
+        assert_eq!(record.signatures, vec!["director"]);
+        assert_eq!(decisions(&record), vec![("manager", Decision::Escalated), ("director", Decision::Approved)]);
+        assert_eq!(record.trail[0].reason, "$7000 exceeds the limit of $5000");
+
+        let record = policy().process(PurchaseRequest::new(35_000, "servers"));
+        assert!(record.is_approved());
+        assert_eq!(record.signatures, vec!["vice-president", "president"]);
+        assert_eq!(decisions(&record), vec![("manager", Decision::Escalated), ("director", Decision::Escalated),
+                                            ("vice-president", Decision::Approved),
+                                            ("president", Decision::CoSigned)]);
+        assert_eq!(record.trail[3].reason, "fine by me");
+
+        let record = policy().process(PurchaseRequest::new(35_000, "company car"));
+        assert_eq!(record.status, Status::Refused {
+            by: "president".to_string(),
+            reason: "company cars are frozen".to_string(),
+        });
+        assert_eq!(record.signatures, vec!["vice-president"]);
+    }
+
+    #[test]
+    fn test_approval_policy_incomplete() {
+        let record = policy().process(PurchaseRequest::new(500_000, "headquarters"));
+        assert_eq!(record.status, Status::Unapproved);
+        assert_eq!(record.trail.len(), 4);
+        assert_eq!(record.trail[2].decision, Decision::CoSigned);
+
+        let policy = ApprovalPolicy::builder()
+            .then(Employee::new("manager", 5000))
+            .then(Employee::new("director", 10_000))
+            .rule(CoSignRule::new(1000, &["director", "auditor"]))
+            .build();
+        assert_eq!(policy.required_signers(&PurchaseRequest::new(2000, "laptop")), vec!["director", "auditor"]);
+        let record = policy.process(PurchaseRequest::new(2000, "laptop"));
+        assert_eq!(record.status, Status::MissingSignatures(vec!["auditor".to_string()]));
+        assert_eq!(decisions(&record), vec![("manager", Decision::Approved), ("director", Decision::CoSigned)]);
+        assert!(policy.process(PurchaseRequest::new(800, "chair")).is_approved());
+
+        let policy = ApprovalPolicy::builder()
+            .then(Employee::new("manager", 5000))
+            .then(Employee::new("director", 10_000))
+            .then(Employee::new("vice-president", 20_000))
+            .rule(CoSignRule::new(1000, &["vice-president"]))
+            .build();
+        let record = policy.process(PurchaseRequest::new(2000, "laptop"));
+        assert!(record.is_approved());
+        assert_eq!(decisions(&record), vec![("manager", Decision::Approved), ("director", Decision::Skipped),
+                                            ("vice-president", Decision::CoSigned)]);
+    }
+}
@@ -0,0 +1,436 @@
+//! Spending budgets for approvers.
+//!
+//! An `Employee` approves any number of requests below its limit. A `BudgetedApprover` additionally has a budget for
+//! every period (a day, a week, ...): each approval draws down the budget, and once it does not cover a request, the
+//! request escalates to the next approver even if it is below the limit. Budgets reset at the start of every period.
+//! The approvers are linked by a `Chain`, which passes every expense along with the period in which it was issued.
+//!
+//! Periods are computed from the time given by a `Clock` of the `clock` module. The `SystemClock` is used by default, a
+//! `ManualClock` makes tests deterministic. Amounts are expressed as `Money`, in cents, such that budgets are drawn
+//! down exactly.
+
+use std::error::Error;
+use std::fmt;
+use std::ops::Sub;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::{Chain, Handler, Outcome, PurchaseRequest};
+use crate::clock::{Clock, SystemClock};
+
+const NANOS_PER_SEC: u128 = 1_000_000_000;
+const SECS_PER_DAY: u64 = 24 * 3600;
+
+/// An exact amount of dollars, stored in cents.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Money {
+    cents: u64,
+}
+
+impl Money {
+    /// No money.
+    pub const ZERO: Money = Money { cents: 0 };
+
+    /// An amount of cents.
+    pub fn from_cents(cents: u64) -> Money {
+        Money { cents }
+    }
+
+    /// An amount of whole dollars, or an error if it cannot be represented in cents.
+    pub fn dollars(dollars: u64) -> Result<Money, MoneyError> {
+        dollars.checked_mul(100).map(Money::from_cents).ok_or(MoneyError::Overflow)
+    }
+
+    /// The amount in cents.
+    pub fn cents(&self) -> u64 {
+        self.cents
+    }
+
+    /// Adds an amount, or returns `None` if the sum cannot be represented.
+    pub fn checked_add(self, other: Money) -> Option<Money> {
+        self.cents.checked_add(other.cents).map(Money::from_cents)
+    }
+
+    /// Subtracts an amount, or returns `None` if it is larger than this one.
+    pub fn checked_sub(self, other: Money) -> Option<Money> {
+        self.cents.checked_sub(other.cents).map(Money::from_cents)
+    }
+}
+
+/// Subtraction saturates at zero, money cannot become negative.
+impl Sub for Money {
+    type Output = Money;
+
+    fn sub(self, other: Money) -> Money {
+        Money::from_cents(self.cents.saturating_sub(other.cents))
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(&format!("${}.{:02}", self.cents / 100, self.cents % 100))
+    }
+}
+
+/// The errors raised when building an amount of money.
+#[derive(Debug, PartialEq, Eq)]
+pub enum MoneyError {
+    /// The text is not an amount of money.
+    Invalid(String),
+    /// The amount is too large to be represented.
+    Overflow,
+}
+
+impl fmt::Display for MoneyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MoneyError::Invalid(ref value)  => write!(f, "invalid amount of money: '{}'", value),
+            MoneyError::Overflow            => write!(f, "amount of money too large"),
+        }
+    }
+}
+
+impl Error for MoneyError {}
+
+/// Parses amounts such as `4999`, `$4_999.99` or `0.5`. At most two decimals are allowed.
+impl FromStr for Money {
+    type Err = MoneyError;
+
+    fn from_str(value: &str) -> Result<Money, MoneyError> {
+        let err = || MoneyError::Invalid(value.to_string());
+        let digits: String = value.trim().trim_start_matches('$').chars().filter(|&c| c != '_').collect();
+        let (whole, fraction) = match digits.find('.') {
+            Some(pos)   => (&digits[..pos], &digits[pos + 1..]),
+            None        => (digits.as_str(), "00"),
+        };
+        let valid = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+        if whole.is_empty() || fraction.is_empty() || fraction.len() > 2 || !valid(whole) || !valid(fraction) {
+            return Err(err());
+        }
+        let dollars: u64 = whole.parse().map_err(|_| err())?;
+        let cents: u64 = format!("{:0<2}", fraction).parse().map_err(|_| err())?;
+        Money::dollars(dollars)?.checked_add(Money::from_cents(cents)).ok_or(MoneyError::Overflow)
+    }
+}
+
+/// A request for an exact amount of money.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Expense {
+    amount: Money,
+    purpose: String,
+}
+
+impl Expense {
+    /// Creates an expense.
+    pub fn new<S>(amount: Money, purpose: S) -> Expense where S: Into<String> {
+        Expense {
+            amount,
+            purpose: purpose.into(),
+        }
+    }
+
+    /// The amount requested.
+    pub fn get_amount(&self) -> Money {
+        self.amount
+    }
+
+    /// What the amount is requested for.
+    pub fn get_purpose(&self) -> &str {
+        &self.purpose
+    }
+}
+
+impl From<PurchaseRequest> for Expense {
+    fn from(request: PurchaseRequest) -> Expense {
+        // A `u32` amount of dollars always fits in `u64` cents.
+        let cents = u64::from(request.get_amount()) * 100;
+        Expense::new(Money::from_cents(cents), request.get_purpose())
+    }
+}
+
+/// The length of a budget period. Periods are aligned on the UNIX epoch, hence daily periods start at midnight UTC.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Period {
+    length: Duration,
+}
+
+impl Period {
+    /// Periods of the given length, which must not be zero.
+    pub fn new(length: Duration) -> Period {
+        assert!(!length.is_zero(), "a period cannot be empty");
+        Period { length }
+    }
+
+    /// Daily periods.
+    ///
+    /// # Panics
+    /// Panics if the length of the period overflows a `Duration`.
+    pub fn days(days: u64) -> Period {
+        Period::new(Duration::from_secs(days.checked_mul(SECS_PER_DAY).expect("the period is too long")))
+    }
+
+    /// Weekly periods, starting on Thursdays as the UNIX epoch.
+    ///
+    /// # Panics
+    /// Panics if the length of the period overflows a `Duration`.
+    pub fn weeks(weeks: u64) -> Period {
+        Period::days(weeks.checked_mul(7).expect("the period is too long"))
+    }
+
+    /// The number of the period containing the given time. The last period never ends after `u64::MAX` of them.
+    pub fn index(&self, now: Duration) -> u64 {
+        u64::try_from(now.as_nanos() / self.length.as_nanos()).unwrap_or(u64::MAX)
+    }
+
+    /// The start of the period with the given number, or `None` if it is beyond the range of a `Duration`.
+    pub fn start(&self, index: u64) -> Option<Duration> {
+        let nanos = self.length.as_nanos().checked_mul(u128::from(index))?;
+        let secs = u64::try_from(nanos / NANOS_PER_SEC).ok()?;
+        let subsec = u32::try_from(nanos % NANOS_PER_SEC).ok()?;
+        Some(Duration::new(secs, subsec))
+    }
+}
+
+/// The spending of an approver over the current period.
+#[derive(Debug)]
+struct Spending {
+    period: u64,
+    spent: Money,
+}
+
+/// An approver with a limit per request and a budget per period.
+#[derive(Debug)]
+pub struct BudgetedApprover {
+    role: String,
+    allowable: Money,
+    budget: Money,
+    spending: Mutex<Spending>,
+}
+
+impl BudgetedApprover {
+    /// Creates an approver approving requests strictly below `allowable`, up to `budget` per period.
+    pub fn new<S>(role: S, allowable: Money, budget: Money) -> BudgetedApprover where S: Into<String> {
+        BudgetedApprover {
+            role: role.into(),
+            allowable,
+            budget,
+            spending: Mutex::new(Spending { period: 0, spent: Money::ZERO }),
+        }
+    }
+
+    /// The role of the approver.
+    pub fn get_role(&self) -> &str {
+        &self.role
+    }
+
+    /// Approves the expense if it is below the limit and covered by the remaining budget of the period, and returns
+    /// the budget left afterwards.
+    fn draw(&self, expense: &Expense, period: u64) -> Option<Money> {
+        if expense.get_amount() >= self.allowable {
+            return None;
+        }
+        let mut spending = self.spending.lock().unwrap();
+        if spending.period != period {
+            *spending = Spending { period, spent: Money::ZERO };
+        }
+        let spent = spending.spent.checked_add(expense.get_amount())?;
+        let remaining = self.budget.checked_sub(spent)?;
+        spending.spent = spent;
+        Some(remaining)
+    }
+
+    /// The amount spent during the given period.
+    fn spent(&self, period: u64) -> Money {
+        let spending = self.spending.lock().unwrap();
+        if spending.period == period { spending.spent } else { Money::ZERO }
+    }
+}
+
+/// The budget of a role over the current period.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BudgetReport {
+    /// The role of the approver.
+    pub role: String,
+    /// The budget per period.
+    pub budget: Money,
+    /// The amount approved during the current period.
+    pub spent: Money,
+    /// The amount left to approve during the current period.
+    pub remaining: Money,
+}
+
+impl fmt::Display for BudgetReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {} of {} remaining", self.role, self.remaining, self.budget)
+    }
+}
+
+/// An expense together with the period in which it was issued, as passed along the chain.
+struct Dated {
+    expense: Expense,
+    period: u64,
+}
+
+/// Links a shared approver into the chain.
+struct Link(Arc<BudgetedApprover>);
+
+impl Handler<Dated> for Link {
+    type Response = Money;
+
+    fn name(&self) -> &str {
+        self.0.get_role()
+    }
+
+    fn handle(&self, request: &Dated) -> Option<Money> {
+        self.0.draw(&request.expense, request.period)
+    }
+}
+
+/// A chain of approvers with budgets. Approving an expense returns the budget left to the approver.
+pub struct BudgetedChain {
+    chain: Chain<Dated, Money>,
+    approvers: Vec<Arc<BudgetedApprover>>,
+    period: Period,
+    clock: Box<dyn Clock>,
+}
+
+impl BudgetedChain {
+    /// Starts building a chain, with daily periods and the system clock by default.
+    pub fn builder() -> BudgetedChainBuilder {
+        BudgetedChainBuilder {
+            approvers: Vec::new(),
+            period: Period::days(1),
+            clock: Box::new(SystemClock),
+        }
+    }
+
+    /// Passes the expense along the chain until an approver below its limit and within its budget approves it.
+    pub fn process(&self, expense: Expense) -> Outcome<Expense, Money> {
+        let period = self.period.index(self.clock.now());
+        match self.chain.process(Dated { expense, period }) {
+            Outcome::Handled { by, response }   => Outcome::Handled { by, response },
+            Outcome::Unhandled(dated)           => Outcome::Unhandled(dated.expense),
+        }
+    }
+
+    /// The start of the current period since the UNIX epoch, or `None` if the clock is beyond the last period.
+    pub fn period_start(&self) -> Option<Duration> {
+        self.period.start(self.period.index(self.clock.now()))
+    }
+
+    /// Reports the budget of every role for the current period, in the order of the chain.
+    pub fn report(&self) -> Vec<BudgetReport> {
+        let period = self.period.index(self.clock.now());
+        self.approvers.iter().map(|approver| {
+            let spent = approver.spent(period);
+            BudgetReport {
+                role: approver.role.clone(),
+                budget: approver.budget,
+                spent,
+                remaining: approver.budget - spent,
+            }
+        }).collect()
+    }
+}
+
+/// Builds a `BudgetedChain`.
+pub struct BudgetedChainBuilder {
+    approvers: Vec<BudgetedApprover>,
+    period: Period,
+    clock: Box<dyn Clock>,
+}
+
+impl BudgetedChainBuilder {
+    /// Appends an approver to the chain.
+    pub fn then(mut self, approver: BudgetedApprover) -> Self {
+        self.approvers.push(approver);
+        self
+    }
+
+    /// Sets the period after which budgets reset.
+    pub fn period(mut self, period: Period) -> Self {
+        self.period = period;
+        self
+    }
+
+    /// Sets the clock used to determine the current period.
+    pub fn clock<C>(mut self, clock: C) -> Self where C: Clock + 'static {
+        self.clock = Box::new(clock);
+        self
+    }
+
+    /// Builds the chain.
+    pub fn build(self) -> BudgetedChain {
+        let approvers: Vec<Arc<BudgetedApprover>> = self.approvers.into_iter().map(Arc::new).collect();
+        let chain = approvers.iter()
+            .fold(Chain::builder().name("budgets"), |chain, approver| chain.then(Link(Arc::clone(approver))))
+            .build();
+        BudgetedChain {
+            chain,
+            approvers,
+            period: self.period,
+            clock: self.clock,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+
+    #[test]
+    fn test_money() {
+        assert_eq!("4999".parse(), Money::dollars(4999));
+        assert_eq!("$4_999.9".parse(), Ok(Money::from_cents(499_990)));
+        assert_eq!("0.05".parse::<Money>().unwrap().to_string(), "$0.05");
+        for invalid in &["", "$", "12.345", "1.", ".5", "-3", "4k"] {
+            assert!(invalid.parse::<Money>().is_err(), "{} should be invalid", invalid);
+        }
+        let dollars = |amount| Money::dollars(amount).unwrap();
+        assert_eq!(dollars(3) - dollars(5), Money::ZERO);
+        assert_eq!(dollars(3).checked_sub(Money::from_cents(1)), Some(Money::from_cents(299)));
+        assert_eq!(format!("{:>8}", dollars(12)), "  $12.00");
+
+        // Amounts that do not fit in cents are refused rather than overflowing.
+        assert_eq!(Money::dollars(u64::MAX / 100 + 1), Err(MoneyError::Overflow));
+        assert_eq!("184467440737095516.16".parse::<Money>(), Err(MoneyError::Overflow));
+        assert_eq!(Money::from_cents(u64::MAX).checked_add(Money::from_cents(1)), None);
+        assert_eq!(Period::days(1).start(u64::MAX), None);
+    }
+
+    #[test]
+    fn test_budgeted_chain() {
+        let clock = ManualClock::new(Duration::from_secs(3600));
+        let dollars = |amount| Money::dollars(amount).unwrap();
+        let chain = BudgetedChain::builder()
+            .then(BudgetedApprover::new("manager", dollars(5000), dollars(10_000)))
+            .then(BudgetedApprover::new("director", dollars(10_000), dollars(50_000)))
+            .period(Period::days(1))
+            .clock(clock.clone())
+            .build();
+        let expense = || Expense::new("4999.99".parse().unwrap(), "monitors");
+
+        assert_eq!(chain.process(expense()), Outcome::Handled {
+            by: "manager".to_string(),
+            response: "5000.01".parse().unwrap(),
+        });
+        assert_eq!(chain.process(expense()).handled_by(), Some("manager"));
+        // The manager has $0.02 left and escalates the third request.
+        assert_eq!(chain.process(expense()).handled_by(), Some("director"));
+        assert_eq!(chain.process(Expense::new(Money::from_cents(2), "stamps")).response(), Some(Money::ZERO));
+        assert!(!chain.process(Expense::new(dollars(10_000), "car")).is_handled());
+
+        let report = chain.report();
+        assert_eq!(report[0].to_string(), "manager: $0.00 of $10000.00 remaining");
+        assert_eq!(report[1].spent, "4999.99".parse().unwrap());
+
+        // Budgets reset at midnight.
+        clock.advance(Duration::from_secs(23 * 3600));
+        assert_eq!(chain.period_start(), Some(Duration::from_secs(24 * 3600)));
+        assert_eq!(chain.report()[0].remaining, dollars(10_000));
+        assert_eq!(chain.process(PurchaseRequest::new(4000, "desk").into()).handled_by(), Some("manager"));
+    }
+}
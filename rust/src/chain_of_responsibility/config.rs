@@ -0,0 +1,321 @@
+//! Approval hierarchies loaded from configuration files.
+//!
+//! Instead of nesting `Employee`s in code, the hierarchy can be described in a TOML-like plain-text file. Each line
+//! maps a role to the amount (exclusive) up to which it approves requests, in the order in which requests are passed
+//! along the chain. The lower bound of a role defaults to the limit of the previous role, it can also be given
+//! explicitly as a range to document it:
+//!
+//! ```text
+//! # Purchase approvals, Q3
+//! [hierarchy]
+//! manager = 5_000
+//! director = 5_000..10_000
+//! "vice-president" = 20_000
+//! president = 40_000
+//! ```
+//!
+//! Blank lines, comments (starting with `#`) and section headers are ignored. The hierarchy is validated when it is
+//! parsed: it cannot be empty, roles must be unique, limits must increase along the chain and the ranges of
+//! consecutive roles must neither leave gaps nor overlap.
+//!
+//! A new hierarchy only replaces the one in use once it was validated, requests in flight are not affected:
+//!
+//! ```no_run
+//! use patterns::chain_of_responsibility::ReloadableChain;
+//! use patterns::chain_of_responsibility::config::HierarchyConfig;
+//!
+//! let approvals = ReloadableChain::new(HierarchyConfig::load("approvals.toml")?.build());
+//! // ... at the start of the next quarter:
+//! approvals.reload(HierarchyConfig::load("approvals.toml")?.build());
+//! # Ok::<(), patterns::chain_of_responsibility::config::ConfigError>(())
+//! ```
+
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::result;
+use std::str::FromStr;
+
+use super::{Chain, Employee, PurchaseRequest};
+
+/// Result of loading a hierarchy.
+pub type Result<T> = result::Result<T, ConfigError>;
+
+/// Errors detected while loading a hierarchy.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ConfigError {
+    /// The file could not be read.
+    Io {
+        /// The path of the file.
+        path: String,
+        /// The reason the file could not be read.
+        message: String,
+    },
+    /// A line is not of the form `role = limit`.
+    Syntax {
+        /// The line number (starting at 1).
+        line: usize,
+        /// What is wrong with the line.
+        message: String,
+    },
+    /// An amount is not a valid number.
+    InvalidAmount {
+        /// The line number (starting at 1).
+        line: usize,
+        /// The invalid amount.
+        value: String,
+    },
+    /// The hierarchy does not contain any role.
+    Empty,
+    /// A role appears twice in the hierarchy.
+    DuplicateRole {
+        /// The duplicated role.
+        role: String,
+        /// The line on which it first appears.
+        first_line: usize,
+        /// The line on which it appears again.
+        line: usize,
+    },
+    /// The limit of a role is not above the limit of the previous role.
+    NotIncreasing {
+        /// The role whose limit is too low.
+        role: String,
+        /// Its limit.
+        allowable: u32,
+        /// The limit it must exceed.
+        previous: u32,
+    },
+    /// No role approves the amounts in `from..to`.
+    Gap {
+        /// The role after the gap.
+        role: String,
+        /// The first amount nobody approves.
+        from: u32,
+        /// The first amount approved again.
+        to: u32,
+    },
+    /// The range of a role starts below the limit of the previous role.
+    Overlap {
+        /// The role whose range overlaps the previous one.
+        role: String,
+        /// The lower bound of the role.
+        from: u32,
+        /// The limit of the previous role.
+        to: u32,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConfigError::Io { ref path, ref message }               => {
+                write!(f, "cannot read {}: {}", path, message)
+            },
+            ConfigError::Syntax { line, ref message }               => write!(f, "line {}: {}", line, message),
+            ConfigError::InvalidAmount { line, ref value }          => {
+                write!(f, "line {}: invalid amount '{}'", line, value)
+            },
+            ConfigError::Empty                                      => write!(f, "the hierarchy contains no role"),
+            ConfigError::DuplicateRole { ref role, first_line, line } => {
+                write!(f, "line {}: role '{}' is already defined on line {}", line, role, first_line)
+            },
+            ConfigError::NotIncreasing { ref role, allowable, previous } => {
+                write!(f, "role '{}' approves up to ${} which is not above ${}", role, allowable, previous)
+            },
+            ConfigError::Gap { ref role, from, to }                 => {
+                write!(f, "nobody approves amounts from ${} to ${} (before role '{}')", from, to, role)
+            },
+            ConfigError::Overlap { ref role, from, to }             => {
+                write!(f, "role '{}' starts at ${} which overlaps the previous role up to ${}", role, from, to)
+            },
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+/// A role of the hierarchy.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RoleLimit {
+    /// The name of the role.
+    pub role: String,
+    /// The amount (exclusive) up to which the role approves requests.
+    pub allowable: u32,
+    /// The line of the configuration defining the role.
+    pub line: usize,
+}
+
+/// A validated approval hierarchy.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HierarchyConfig {
+    roles: Vec<RoleLimit>,
+}
+
+impl HierarchyConfig {
+    /// Reads and validates a hierarchy from a file.
+    pub fn load<P>(path: P) -> Result<HierarchyConfig> where P: AsRef<Path> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path).map_err(|err| ConfigError::Io {
+            path: path.display().to_string(),
+            message: err.to_string(),
+        })?;
+        source.parse()
+    }
+
+    /// The roles of the hierarchy, in the order in which they handle requests.
+    pub fn roles(&self) -> &[RoleLimit] {
+        &self.roles
+    }
+
+    /// Builds the chain of `Employee`s described by the hierarchy.
+    pub fn build(&self) -> Chain<PurchaseRequest, String> {
+        self.roles.iter()
+            .fold(Chain::builder().name("hierarchy"), |builder, role| {
+                builder.then(Employee::new(role.role.clone(), role.allowable))
+            })
+            .build()
+    }
+}
+
+impl FromStr for HierarchyConfig {
+    type Err = ConfigError;
+
+    fn from_str(source: &str) -> Result<HierarchyConfig> {
+        let mut roles: Vec<RoleLimit> = Vec::new();
+        for (idx, raw) in source.lines().enumerate() {
+            let line = idx + 1;
+            let content = raw.split('#').next().unwrap_or("").trim();
+            if content.is_empty() || (content.starts_with('[') && content.ends_with(']')) {
+                continue;
+            }
+
+            let (key, value) = match content.find('=') {
+                Some(pos)   => (unquote(&content[..pos]), content[pos + 1..].trim()),
+                None        => return Err(syntax(line, "expected 'role = limit'")),
+            };
+            if key.is_empty() {
+                return Err(syntax(line, "missing role"));
+            }
+            if let Some(first) = roles.iter().find(|role| role.role == key) {
+                return Err(ConfigError::DuplicateRole { role: key.to_string(), first_line: first.line, line });
+            }
+
+            let lower = roles.last().map_or(0, |role| role.allowable);
+            let (from, allowable) = match value.find("..") {
+                Some(pos)   => (Some(amount(line, &value[..pos])?), amount(line, &value[pos + 2..])?),
+                None        => (None, amount(line, value)?),
+            };
+            match from {
+                Some(from) if from > lower  => {
+                    return Err(ConfigError::Gap { role: key.to_string(), from: lower, to: from });
+                },
+                Some(from) if from < lower  => {
+                    return Err(ConfigError::Overlap { role: key.to_string(), from, to: lower });
+                },
+                _                           => (),
+            }
+            if allowable <= lower {
+                return Err(ConfigError::NotIncreasing { role: key.to_string(), allowable, previous: lower });
+            }
+            roles.push(RoleLimit { role: key.to_string(), allowable, line });
+        }
+
+        if roles.is_empty() {
+            return Err(ConfigError::Empty);
+        }
+        Ok(HierarchyConfig { roles })
+    }
+}
+
+fn syntax(line: usize, message: &str) -> ConfigError {
+    ConfigError::Syntax { line, message: message.to_string() }
+}
+
+/// Removes the whitespace and the quotes around a role.
+fn unquote(value: &str) -> &str {
+    let value = value.trim();
+    if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+        &value[1..value.len() - 1]
+    } else {
+        value
+    }
+}
+
+/// Parses an amount, allowing underscores as digit separators.
+fn amount(line: usize, value: &str) -> Result<u32> {
+    let value = unquote(value);
+    let digits: String = value.chars().filter(|&c| c != '_').collect();
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return Err(ConfigError::InvalidAmount { line, value: value.to_string() });
+    }
+    digits.parse().map_err(|_| ConfigError::InvalidAmount { line, value: value.to_string() })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{Outcome, ReloadableChain};
+
+    const HIERARCHY: &str = "# Purchase approvals\n\
+                             [hierarchy]\n\
+                             manager = 5_000\n\
+                             director = 5_000..10_000   # explicit lower bound\n\
+                             \"vice-president\" = 20_000\n\
+                             \n\
+                             president = 40000\n";
+
+    #[test]
+    fn test_hierarchy_config() {
+        let config: HierarchyConfig = HIERARCHY.parse().unwrap();
+        let roles: Vec<(&str, u32)> = config.roles().iter().map(|role| (role.role.as_str(), role.allowable)).collect();
+        assert_eq!(roles, vec![("manager", 5000), ("director", 10_000), ("vice-president", 20_000),
+                               ("president", 40_000)]);
+        assert_eq!(config.roles()[3].line, 7);
+
+        let chain = config.build();
+        let outcome = chain.process(PurchaseRequest::new(12_000, "general expenses"));
+        assert_eq!(outcome.handled_by(), Some("vice-president"));
+        assert!(!chain.process(PurchaseRequest::new(40_000, "company car")).is_handled());
+    }
+
+    #[test]
+    fn test_hierarchy_config_errors() {
+        let parse = |source: &str| source.parse::<HierarchyConfig>().unwrap_err();
+
+        assert_eq!(parse("# nothing here\n[hierarchy]\n"), ConfigError::Empty);
+        assert_eq!(parse("manager 5000"), ConfigError::Syntax { line: 1, message: "expected 'role = limit'".into() });
+        assert_eq!(parse(" = 5000"), ConfigError::Syntax { line: 1, message: "missing role".into() });
+        assert_eq!(parse("manager = 5k"), ConfigError::InvalidAmount { line: 1, value: "5k".into() });
+        assert_eq!(parse("manager = 5000\nmanager = 8000"),
+                   ConfigError::DuplicateRole { role: "manager".into(), first_line: 1, line: 2 });
+        assert_eq!(parse("manager = 5000\ndirector = 5000"),
+                   ConfigError::NotIncreasing { role: "director".into(), allowable: 5000, previous: 5000 });
+        assert_eq!(parse("manager = 100..5000"), ConfigError::Gap { role: "manager".into(), from: 0, to: 100 });
+        assert_eq!(parse("manager = 5000\ndirector = 6000..10000"),
+                   ConfigError::Gap { role: "director".into(), from: 5000, to: 6000 });
+        assert_eq!(parse("manager = 5000\ndirector = 4000..10000"),
+                   ConfigError::Overlap { role: "director".into(), from: 4000, to: 5000 });
+
+        let err = HierarchyConfig::load("/nonexistent/hierarchy.toml").unwrap_err();
+        assert!(err.to_string().starts_with("cannot read /nonexistent/hierarchy.toml: "));
+    }
+
+    #[test]
+    fn test_hierarchy_reload() {
+        let reloadable = ReloadableChain::new(HIERARCHY.parse::<HierarchyConfig>().unwrap().build());
+        let in_flight = reloadable.snapshot();
+
+        let next_quarter: HierarchyConfig = "manager = 8000\npresident = 100000".parse().unwrap();
+        reloadable.reload(next_quarter.build());
+
+        let request = PurchaseRequest::new(7000, "printer");
+        assert_eq!(in_flight.process(request.clone()).handled_by(), Some("director"));
+        assert_eq!(reloadable.process(request).handled_by(), Some("manager"));
+        match reloadable.process(PurchaseRequest::new(50_000, "company car")) {
+            Outcome::Handled { by, .. } => assert_eq!(by, "president"),
+            Outcome::Unhandled(_)       => panic!("the new hierarchy approves up to $100000"),
+        }
+    }
+}
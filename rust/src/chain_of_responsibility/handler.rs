@@ -0,0 +1,258 @@
+//! Generic chain of responsibility.
+//!
+//! `PurchasePower` ties handlers to `PurchaseRequest`s and reports its result as a sentence. The `Handler` trait below
+//! works for any request type and lets a handler return any response. Handlers do not know about their successor:
+//! they are linked together by a `Chain`, which is built once using a `ChainBuilder` instead of nesting handlers by
+//! hand. The result of passing a request along a chain is an `Outcome`, telling which handler took care of it or
+//! handing the request back to the caller if none did.
+//!
+//! A `ReloadableChain` allows to replace a chain at runtime. Every request is processed by a snapshot of the chain
+//! taken when it was issued, hence requests in flight during a reload complete on the chain they started on.
+
+use std::fmt;
+use std::sync::{Arc, RwLock};
+
+/// A processing object able to handle some requests of type `Req`.
+pub trait Handler<Req> {
+    /// The response produced when the handler takes care of a request.
+    type Response;
+
+    /// The name under which the handler appears in outcomes.
+    fn name(&self) -> &str;
+
+    /// Handles the request, or returns `None` to let the next handler in the chain try.
+    fn handle(&self, request: &Req) -> Option<Self::Response>;
+}
+
+/// A boxed handler as stored in a `Chain`. Handlers must be thread-safe so that chains can be shared by the threads
+/// issuing requests.
+pub type BoxedHandler<Req, Resp> = Box<dyn Handler<Req, Response = Resp> + Send + Sync>;
+
+/// The result of passing a request along a chain.
+#[derive(Debug, PartialEq)]
+pub enum Outcome<Req, Resp> {
+    /// A handler took care of the request.
+    Handled {
+        /// The name of the handler.
+        by: String,
+        /// The response of the handler.
+        response: Resp,
+    },
+    /// No handler in the chain took care of the request, it is handed back to the caller.
+    Unhandled(Req),
+}
+
+impl<Req, Resp> Outcome<Req, Resp> {
+    /// Whether a handler took care of the request.
+    pub fn is_handled(&self) -> bool {
+        matches!(*self, Outcome::Handled { .. })
+    }
+
+    /// Returns the name of the handler that took care of the request, if any.
+    pub fn handled_by(&self) -> Option<&str> {
+        match *self {
+            Outcome::Handled { ref by, .. } => Some(by),
+            Outcome::Unhandled(_)           => None,
+        }
+    }
+
+    /// Returns the response of the handler that took care of the request, if any.
+    pub fn response(self) -> Option<Resp> {
+        match self {
+            Outcome::Handled { response, .. }   => Some(response),
+            Outcome::Unhandled(_)               => None,
+        }
+    }
+}
+
+/// An ordered list of handlers. Requests are passed to the handlers in order until one of them handles it.
+pub struct Chain<Req, Resp> {
+    name: String,
+    handlers: Vec<BoxedHandler<Req, Resp>>,
+}
+
+impl<Req, Resp> Chain<Req, Resp> {
+    /// Starts building a chain.
+    pub fn builder() -> ChainBuilder<Req, Resp> {
+        ChainBuilder {
+            name: String::from("chain"),
+            handlers: Vec::new(),
+        }
+    }
+
+    /// Passes the request along the chain.
+    pub fn process(&self, request: Req) -> Outcome<Req, Resp> {
+        for handler in &self.handlers {
+            if let Some(response) = handler.handle(&request) {
+                return Outcome::Handled {
+                    by: handler.name().to_string(),
+                    response,
+                };
+            }
+        }
+        Outcome::Unhandled(request)
+    }
+
+    /// The number of handlers in the chain.
+    pub fn len(&self) -> usize {
+        self.handlers.len()
+    }
+
+    /// Whether the chain contains no handlers.
+    pub fn is_empty(&self) -> bool {
+        self.handlers.is_empty()
+    }
+}
+
+/// A chain is itself a handler, hence chains can be nested in other chains.
+impl<Req, Resp> Handler<Req> for Chain<Req, Resp> {
+    type Response = Resp;
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn handle(&self, request: &Req) -> Option<Resp> {
+        self.handlers.iter().filter_map(|handler| handler.handle(request)).next()
+    }
+}
+
+impl<Req, Resp> fmt::Debug for Chain<Req, Resp> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names: Vec<&str> = self.handlers.iter().map(|handler| handler.name()).collect();
+        f.debug_struct("Chain").field("name", &self.name).field("handlers", &names).finish()
+    }
+}
+
+/// Builds a `Chain` from its handlers, in the order in which they should be tried.
+pub struct ChainBuilder<Req, Resp> {
+    name: String,
+    handlers: Vec<BoxedHandler<Req, Resp>>,
+}
+
+impl<Req, Resp> ChainBuilder<Req, Resp> {
+    /// Sets the name of the chain, used when it is nested in another chain.
+    pub fn name<S>(mut self, name: S) -> Self where S: Into<String> {
+        self.name = name.into();
+        self
+    }
+
+    /// Appends a handler to the chain.
+    pub fn then<H>(mut self, handler: H) -> Self where H: Handler<Req, Response = Resp> + Send + Sync + 'static {
+        self.handlers.push(Box::new(handler));
+        self
+    }
+
+    /// Appends an already boxed handler to the chain.
+    pub fn then_boxed(mut self, handler: BoxedHandler<Req, Resp>) -> Self {
+        self.handlers.push(handler);
+        self
+    }
+
+    /// Builds the chain.
+    pub fn build(self) -> Chain<Req, Resp> {
+        Chain {
+            name: self.name,
+            handlers: self.handlers,
+        }
+    }
+}
+
+/// A chain that can be replaced while requests are being processed, possibly from other threads.
+pub struct ReloadableChain<Req, Resp> {
+    current: RwLock<Arc<Chain<Req, Resp>>>,
+}
+
+impl<Req, Resp> ReloadableChain<Req, Resp> {
+    /// Wraps a chain to make it reloadable.
+    pub fn new(chain: Chain<Req, Resp>) -> ReloadableChain<Req, Resp> {
+        ReloadableChain {
+            current: RwLock::new(Arc::new(chain)),
+        }
+    }
+
+    /// Returns the chain currently in use. The snapshot stays valid after a reload.
+    pub fn snapshot(&self) -> Arc<Chain<Req, Resp>> {
+        self.current.read().unwrap().clone()
+    }
+
+    /// Passes the request along the chain currently in use.
+    pub fn process(&self, request: Req) -> Outcome<Req, Resp> {
+        self.snapshot().process(request)
+    }
+
+    /// Replaces the chain. Requests issued from now on use the new chain, requests in flight complete on the old one.
+    /// Returns the chain that was replaced.
+    pub fn reload(&self, chain: Chain<Req, Resp>) -> Arc<Chain<Req, Resp>> {
+        let mut current = self.current.write().unwrap();
+        std::mem::replace(&mut *current, Arc::new(chain))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Handles numbers divisible by some divisor.
+    struct Divisible(u32, &'static str);
+    impl Handler<u32> for Divisible {
+        type Response = u32;
+
+        fn name(&self) -> &str {
+            self.1
+        }
+
+        fn handle(&self, request: &u32) -> Option<u32> {
+            if request.is_multiple_of(self.0) { Some(request / self.0) } else { None }
+        }
+    }
+
+    #[test]
+    fn test_handler_chain() {
+        let chain = Chain::builder()
+            .then(Divisible(5, "five"))
+            .then(Chain::builder().name("small").then(Divisible(3, "three")).then(Divisible(2, "two")).build())
+            .build();
+
+        assert_eq!(chain.len(), 2);
+        assert_eq!(chain.process(25), Outcome::Handled { by: "five".to_string(), response: 5 });
+        assert_eq!(chain.process(9), Outcome::Handled { by: "small".to_string(), response: 3 });
+        assert_eq!(chain.process(7), Outcome::Unhandled(7));
+        assert_eq!(chain.process(4).response(), Some(2));
+
+        let empty: Chain<u32, u32> = Chain::builder().build();
+        assert!(empty.is_empty());
+        assert!(!empty.process(1).is_handled());
+    }
+
+    #[test]
+    fn test_reloadable_chain() {
+        use std::sync::mpsc;
+        use std::thread;
+
+        let reloadable = Arc::new(ReloadableChain::new(Chain::builder().then(Divisible(2, "two")).build()));
+        assert_eq!(reloadable.process(4).handled_by(), Some("two"));
+
+        // A request in flight holds the snapshot it started on while the chain is reloaded.
+        let (started, start) = mpsc::channel();
+        let (reloaded, reload) = mpsc::channel();
+        let worker = {
+            let reloadable = Arc::clone(&reloadable);
+            thread::spawn(move || {
+                let chain = reloadable.snapshot();
+                started.send(()).unwrap();
+                reload.recv().unwrap();
+                chain.process(6)
+            })
+        };
+        start.recv().unwrap();
+        let old = reloadable.reload(Chain::builder().then(Divisible(3, "three")).build());
+        reloaded.send(()).unwrap();
+
+        assert_eq!(worker.join().unwrap().handled_by(), Some("two"));
+        assert_eq!(old.len(), 1);
+        assert_eq!(reloadable.process(6).handled_by(), Some("three"));
+        assert_eq!(reloadable.process(4), Outcome::Unhandled(4));
+    }
+}
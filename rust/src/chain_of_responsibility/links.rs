@@ -0,0 +1,309 @@
+//! Inspection and edition of linked `PurchasePower` chains.
+//!
+//! Handlers linked with `set_successor` form a singly linked list, in which each handler owns its successor. A
+//! `PurchaseChain` owns the head of such a list and gives access to its members: they can be iterated over, searched by
+//! role and rendered, handlers can be inserted or removed anywhere in the chain, and `who_would_handle` previews the
+//! routing of an amount without processing any request.
+//!
+//! Editing the chain relies on `take_successor`. A handler which does not implement it cannot be separated from its
+//! successors, hence handlers can neither be inserted after it nor removed from its successors.
+
+use std::fmt;
+
+use super::{PurchasePower, PurchaseRequest};
+
+/// A chain of linked `PurchasePower` handlers.
+#[derive(Default)]
+pub struct PurchaseChain {
+    head: Option<Box<dyn PurchasePower>>,
+}
+
+impl PurchaseChain {
+    /// Creates an empty chain.
+    pub fn new() -> PurchaseChain {
+        PurchaseChain { head: None }
+    }
+
+    /// Wraps handlers that are already linked, starting with the given one.
+    pub fn from_head(head: Box<dyn PurchasePower>) -> PurchaseChain {
+        PurchaseChain { head: Some(head) }
+    }
+
+    /// Returns the first handler of the chain, which still owns all its successors.
+    pub fn into_head(self) -> Option<Box<dyn PurchasePower>> {
+        self.head
+    }
+
+    /// Iterates over the handlers, in the order in which they receive requests.
+    pub fn iter(&self) -> Members<'_> {
+        Members { next: self.head.as_deref() }
+    }
+
+    /// The number of handlers in the chain.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Whether the chain contains no handlers.
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    /// The roles of the handlers, in order.
+    pub fn roles(&self) -> Vec<&str> {
+        self.iter().map(|handler| handler.get_role()).collect()
+    }
+
+    /// Returns the position of the first handler with the given role.
+    pub fn position(&self, role: &str) -> Option<usize> {
+        self.iter().position(|handler| handler.get_role() == role)
+    }
+
+    /// Returns the first handler with the given role.
+    pub fn find(&self, role: &str) -> Option<&dyn PurchasePower> {
+        self.iter().find(|handler| handler.get_role() == role)
+    }
+
+    /// Returns the handler that would approve a request for the given amount, without processing any request.
+    pub fn who_would_handle(&self, amount: u32) -> Option<&dyn PurchasePower> {
+        self.iter().find(|handler| handler.can_approve(amount))
+    }
+
+    /// Passes the request along the chain.
+    pub fn process_request(&self, request: PurchaseRequest) -> String {
+        match self.head {
+            Some(ref head)  => head.process_request(request),
+            None            => String::from("Request amount is too high"),
+        }
+    }
+
+    /// Appends a handler at the end of the chain. Returns the handler back if the last handler cannot be unlinked
+    /// from its successors.
+    pub fn push(&mut self, handler: Box<dyn PurchasePower>) -> Result<(), Box<dyn PurchasePower>> {
+        let len = self.len();
+        self.insert(len, handler)
+    }
+
+    /// Inserts a handler at the given position, shifting the handlers after it. Any successor of the inserted handler
+    /// is replaced. Returns the handler back if `index` is greater than the length of the chain, or if the position
+    /// follows a handler which cannot be unlinked from its successors.
+    pub fn insert(&mut self, index: usize, handler: Box<dyn PurchasePower>) -> Result<(), Box<dyn PurchasePower>> {
+        if index > self.len() {
+            return Err(handler);
+        }
+        let mut handlers = self.unlink();
+        if index > handlers.len() || (index == handlers.len() && !detached(&handlers)) {
+            self.link(handlers);
+            return Err(handler);
+        }
+        handlers.insert(index, handler);
+        self.link(handlers);
+        Ok(())
+    }
+
+    /// Inserts a handler before the first handler with the given role. Returns the handler back if the role is not
+    /// part of the chain, or if the handler cannot be inserted there.
+    pub fn insert_before(&mut self, role: &str, handler: Box<dyn PurchasePower>)
+        -> Result<(), Box<dyn PurchasePower>> {
+        match self.position(role) {
+            Some(index) => self.insert(index, handler),
+            None        => Err(handler),
+        }
+    }
+
+    /// Inserts a handler after the first handler with the given role. Returns the handler back if the role is not part
+    /// of the chain, or if the handler cannot be inserted there.
+    pub fn insert_after(&mut self, role: &str, handler: Box<dyn PurchasePower>)
+        -> Result<(), Box<dyn PurchasePower>> {
+        match self.position(role) {
+            Some(index) => self.insert(index + 1, handler),
+            None        => Err(handler),
+        }
+    }
+
+    /// Removes the first handler with the given role and returns it, unlinked from the rest of the chain. Returns
+    /// `None` if the role is not part of the chain, or if the handler cannot be unlinked from its neighbours.
+    pub fn remove(&mut self, role: &str) -> Option<Box<dyn PurchasePower>> {
+        let index = self.position(role)?;
+        let mut handlers = self.unlink();
+        if index >= handlers.len() || (index + 1 == handlers.len() && !detached(&handlers)) {
+            self.link(handlers);
+            return None;
+        }
+        let removed = handlers.remove(index);
+        self.link(handlers);
+        Some(removed)
+    }
+
+    /// Unlinks the handlers of the chain, leaving it empty. The last handler keeps its successors if it cannot be
+    /// unlinked from them.
+    fn unlink(&mut self) -> Vec<Box<dyn PurchasePower>> {
+        let mut handlers = Vec::new();
+        let mut next = self.head.take();
+        while let Some(mut handler) = next {
+            next = handler.take_successor();
+            handlers.push(handler);
+        }
+        handlers
+    }
+
+    /// Links the handlers in order and makes them the chain.
+    fn link(&mut self, handlers: Vec<Box<dyn PurchasePower>>) {
+        self.head = handlers.into_iter().rev().fold(None, |next, mut handler| {
+            if let Some(next) = next {
+                handler.set_successor(next);
+            } else {
+                handler.take_successor();
+            }
+            Some(handler)
+        });
+    }
+}
+
+/// Whether the unlinked handlers are all separated from their successors.
+fn detached(handlers: &[Box<dyn PurchasePower>]) -> bool {
+    handlers.last().is_none_or(|last| last.get_successor().is_none())
+}
+
+/// Renders the chain as `manager (< $5000) -> director (< $10000)`.
+impl fmt::Display for PurchaseChain {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (idx, handler) in self.iter().enumerate() {
+            if idx > 0 {
+                write!(f, " -> ")?;
+            }
+            write!(f, "{} (< ${})", handler.get_role(), handler.get_allowable())?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for PurchaseChain {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.roles()).finish()
+    }
+}
+
+/// Iterator over the handlers of a `PurchaseChain`.
+pub struct Members<'a> {
+    next: Option<&'a dyn PurchasePower>,
+}
+
+impl<'a> Iterator for Members<'a> {
+    type Item = &'a dyn PurchasePower;
+
+    fn next(&mut self) -> Option<&'a dyn PurchasePower> {
+        let current = self.next?;
+        self.next = current.get_successor();
+        Some(current)
+    }
+}
+
+impl<'a> IntoIterator for &'a PurchaseChain {
+    type Item = &'a dyn PurchasePower;
+    type IntoIter = Members<'a>;
+
+    fn into_iter(self) -> Members<'a> {
+        self.iter()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::Employee;
+
+    #[test]
+    fn test_purchase_chain() {
+        let mut manager = Employee::new("manager", 5000);
+        manager.set_successor(Box::new(Employee::new("director", 10_000)));
+        let mut chain = PurchaseChain::from_head(Box::new(manager));
+        assert!(chain.push(Box::new(Employee::new("president", 40_000))).is_ok());
+
+        assert_eq!(chain.roles(), vec!["manager", "director", "president"]);
+        assert_eq!(chain.who_would_handle(15_000).map(|handler| handler.get_role()), Some("president"));
+
+        assert!(chain.insert_after("director", Box::new(Employee::new("vice-president", 20_000))).is_ok());
+        assert!(chain.insert_before("intern", Box::new(Employee::new("assistant", 100))).is_err());
+        assert_eq!(chain.to_string(),
+                   "manager (< $5000) -> director (< $10000) -> vice-president (< $20000) -> president (< $40000)");
+        assert_eq!(chain.who_would_handle(15_000).map(|handler| handler.get_role()), Some("vice-president"));
+        assert_eq!(chain.process_request(PurchaseRequest::new(15_000, "servers")),
+                   "vice-president will approve $15000 for servers");
+
+        let director = chain.remove("director").unwrap();
+        assert!(director.get_successor().is_none());
+        assert_eq!(chain.find("vice-president").unwrap().get_allowable(), 20_000);
+        assert_eq!(chain.who_would_handle(7000).map(|handler| handler.get_role()), Some("vice-president"));
+
+        chain.remove("manager");
+        assert!(chain.insert(0, director).is_ok());
+        assert_eq!(chain.position("president"), Some(2));
+        assert!(chain.who_would_handle(40_000).is_none());
+        assert_eq!(format!("{:?}", chain), r#"["director", "vice-president", "president"]"#);
+    }
+
+    #[test]
+    fn test_purchase_chain_empty() {
+        let mut chain = PurchaseChain::new();
+        assert!(chain.is_empty());
+        assert!(chain.remove("manager").is_none());
+        assert_eq!(chain.process_request(PurchaseRequest::new(1, "pen")), "Request amount is too high");
+
+        // Out of bounds insertions leave the chain untouched.
+        assert!(chain.insert(1, Box::new(Employee::new("director", 10_000))).is_err());
+        assert!(chain.insert(0, Box::new(Employee::new("manager", 5000))).is_ok());
+        assert_eq!(chain.insert(2, Box::new(Employee::new("director", 10_000))).unwrap_err().get_role(), "director");
+        assert_eq!(chain.len(), 1);
+        assert_eq!(chain.into_head().unwrap().get_role(), "manager");
+    }
+
+    /// Approves round amounts only, and cannot be unlinked from its successor.
+    struct Auditor {
+        successor: Option<Box<dyn PurchasePower>>,
+    }
+
+    impl PurchasePower for Auditor {
+        fn set_successor(&mut self, successor: Box<dyn PurchasePower>) {
+            self.successor = Some(successor);
+        }
+
+        fn get_successor(&self) -> Option<&dyn PurchasePower> {
+            self.successor.as_deref()
+        }
+
+        fn can_approve(&self, amount: u32) -> bool {
+            amount.is_multiple_of(1000)
+        }
+
+        fn get_allowable(&self) -> u32 {
+            u32::MAX
+        }
+
+        fn get_role(&self) -> &str {
+            "auditor"
+        }
+    }
+
+    #[test]
+    fn test_purchase_chain_custom_handler() {
+        let mut auditor = Auditor { successor: None };
+        auditor.set_successor(Box::new(Employee::new("president", 40_000)));
+        let mut chain = PurchaseChain::from_head(Box::new(auditor));
+        assert!(chain.insert(0, Box::new(Employee::new("manager", 500))).is_ok());
+
+        assert_eq!(chain.who_would_handle(3000).map(|handler| handler.get_role()), Some("auditor"));
+        assert_eq!(chain.who_would_handle(3001).map(|handler| handler.get_role()), Some("president"));
+        assert_eq!(chain.process_request(PurchaseRequest::new(3000, "audit")), "auditor will approve $3000 for audit");
+
+        // The president cannot be separated from the auditor.
+        assert!(chain.push(Box::new(Employee::new("board", 100_000))).is_err());
+        assert!(chain.insert_after("auditor", Box::new(Employee::new("board", 100_000))).is_err());
+        assert!(chain.remove("president").is_none());
+        assert!(chain.remove("auditor").is_none());
+        assert_eq!(chain.roles(), vec!["manager", "auditor", "president"]);
+        assert_eq!(chain.remove("manager").unwrap().get_role(), "manager");
+        assert_eq!(chain.roles(), vec!["auditor", "president"]);
+    }
+}
@@ -0,0 +1,347 @@
+//! Hierarchical logging as a tree of responsibilities.
+//!
+//! Loggers are named hierarchically, using dots as separators: `app.db` is the parent of `app.db.pool`, and the root
+//! logger (named by the empty string) is the ancestor of all loggers. A record is dispatched to the logger it was
+//! issued on and then propagated to its ancestors. Unlike in a chain, handling a record does not stop it: every logger
+//! on the way writes it to all of its targets, such that a single record fans out to several handlers.
+//!
+//! Each logger decides on its own whether it handles a record:
+//! - its level is the minimal level of the records it handles. Loggers without a level inherit the one of their
+//!   closest ancestor having one, the root logger handles everything by default.
+//! - its filters must all accept the record.
+//!
+//! A logger rejecting a record still propagates it to its parent. Propagation itself can be disabled per logger, in
+//! which case the record does not go further up the tree.
+//!
+//! Records are written to `Target`s. Targets writing to the standard error, to a file and to an in-memory buffer are
+//! provided.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// The severity of a record.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    /// Very detailed information, usually only useful when tracing a specific problem.
+    Trace,
+    /// Information useful when debugging.
+    Debug,
+    /// Information on the normal operation.
+    Info,
+    /// Something unexpected happened, but the operation continues.
+    Warn,
+    /// An operation failed.
+    Error,
+}
+
+impl Level {
+    /// The name of the level in uppercase.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Level::Trace    => "TRACE",
+            Level::Debug    => "DEBUG",
+            Level::Info     => "INFO",
+            Level::Warn     => "WARN",
+            Level::Error    => "ERROR",
+        }
+    }
+
+    /// Parses a level from its name, ignoring case.
+    pub fn from_name(name: &str) -> Option<Level> {
+        match name.to_uppercase().as_str() {
+            "TRACE"             => Some(Level::Trace),
+            "DEBUG"             => Some(Level::Debug),
+            "INFO"              => Some(Level::Info),
+            "WARN" | "WARNING"  => Some(Level::Warn),
+            "ERROR"             => Some(Level::Error),
+            _                   => None,
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+/// A log record.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Record {
+    /// The severity of the record.
+    pub level: Level,
+    /// The name of the logger the record was issued on.
+    pub logger: String,
+    /// The message.
+    pub message: String,
+}
+
+impl Record {
+    /// Creates a record.
+    pub fn new<L, M>(level: Level, logger: L, message: M) -> Record where L: Into<String>, M: Into<String> {
+        Record {
+            level,
+            logger: logger.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let logger = if self.logger.is_empty() { "root" } else { &self.logger };
+        write!(f, "{:<5} {}: {}", self.level, logger, self.message)
+    }
+}
+
+/// The destination of the records handled by a logger.
+pub trait Target: Send + Sync {
+    /// Writes a record.
+    fn write(&self, record: &Record) -> io::Result<()>;
+}
+
+/// Writes records to the standard error, one per line.
+#[derive(Debug, Default)]
+pub struct StderrTarget;
+
+impl Target for StderrTarget {
+    fn write(&self, record: &Record) -> io::Result<()> {
+        writeln!(io::stderr().lock(), "{}", record)
+    }
+}
+
+/// Appends records to a file, one per line.
+#[derive(Debug)]
+pub struct FileTarget {
+    file: Mutex<File>,
+}
+
+impl FileTarget {
+    /// Opens a file for appending, creating it if needed.
+    pub fn open<P>(path: P) -> io::Result<FileTarget> where P: AsRef<Path> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(FileTarget { file: Mutex::new(file) })
+    }
+}
+
+impl Target for FileTarget {
+    fn write(&self, record: &Record) -> io::Result<()> {
+        writeln!(self.file.lock().unwrap(), "{}", record)
+    }
+}
+
+/// Keeps records in memory. Clones share the same buffer, hence a clone can be kept to inspect the records written by
+/// the logger owning the target.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryTarget {
+    records: Arc<Mutex<Vec<Record>>>,
+}
+
+impl MemoryTarget {
+    /// Creates an empty buffer.
+    pub fn new() -> MemoryTarget {
+        MemoryTarget::default()
+    }
+
+    /// Returns the records written so far.
+    pub fn records(&self) -> Vec<Record> {
+        self.records.lock().unwrap().clone()
+    }
+
+    /// Returns the records written so far, formatted as lines.
+    pub fn lines(&self) -> Vec<String> {
+        self.records.lock().unwrap().iter().map(|record| record.to_string()).collect()
+    }
+}
+
+impl Target for MemoryTarget {
+    fn write(&self, record: &Record) -> io::Result<()> {
+        self.records.lock().unwrap().push(record.clone());
+        Ok(())
+    }
+}
+
+/// A predicate deciding whether a logger handles a record.
+pub type Filter = Box<dyn Fn(&Record) -> bool + Send + Sync>;
+
+/// A node of the logger tree.
+pub struct Logger {
+    level: Option<Level>,
+    targets: Vec<Box<dyn Target>>,
+    filters: Vec<Filter>,
+    propagate: bool,
+}
+
+impl Logger {
+    fn new() -> Logger {
+        Logger {
+            level: None,
+            targets: Vec::new(),
+            filters: Vec::new(),
+            propagate: true,
+        }
+    }
+
+    /// Sets the minimal level of the records handled by the logger.
+    pub fn level(&mut self, level: Level) -> &mut Logger {
+        self.level = Some(level);
+        self
+    }
+
+    /// Adds a target to which the logger writes the records it handles.
+    pub fn target<T>(&mut self, target: T) -> &mut Logger where T: Target + 'static {
+        self.targets.push(Box::new(target));
+        self
+    }
+
+    /// Adds a filter. The logger only handles the records accepted by all its filters.
+    pub fn filter<F>(&mut self, filter: F) -> &mut Logger where F: Fn(&Record) -> bool + Send + Sync + 'static {
+        self.filters.push(Box::new(filter));
+        self
+    }
+
+    /// Sets whether records are passed on to the parent logger. This is the case by default.
+    pub fn propagate(&mut self, propagate: bool) -> &mut Logger {
+        self.propagate = propagate;
+        self
+    }
+}
+
+/// The tree of loggers dispatching records.
+pub struct LoggerTree {
+    loggers: HashMap<String, Logger>,
+}
+
+impl LoggerTree {
+    /// Creates a tree containing only the root logger, without any target.
+    pub fn new() -> LoggerTree {
+        let mut loggers = HashMap::new();
+        loggers.insert(String::new(), Logger::new());
+        LoggerTree { loggers }
+    }
+
+    /// Returns the logger with the given name for configuration, creating it if needed. The root logger is named by
+    /// the empty string.
+    pub fn logger(&mut self, name: &str) -> &mut Logger {
+        self.loggers.entry(name.to_string()).or_insert_with(Logger::new)
+    }
+
+    /// The level below which the logger with the given name ignores records.
+    pub fn effective_level(&self, name: &str) -> Level {
+        ancestry(name)
+            .filter_map(|name| self.loggers.get(name).and_then(|logger| logger.level))
+            .next()
+            .unwrap_or(Level::Trace)
+    }
+
+    /// Dispatches a record along the tree, starting at the logger it was issued on. Returns the number of targets the
+    /// record was written to. All handling loggers are tried even if a target fails, the first failure is returned.
+    pub fn dispatch(&self, record: &Record) -> io::Result<usize> {
+        let mut written = 0;
+        let mut failure = None;
+        for name in ancestry(&record.logger) {
+            let logger = match self.loggers.get(name) {
+                Some(logger)    => logger,
+                None            => continue,
+            };
+            if record.level >= self.effective_level(name) && logger.filters.iter().all(|filter| filter(record)) {
+                for target in &logger.targets {
+                    match target.write(record) {
+                        Ok(())      => written += 1,
+                        Err(err)    => failure = failure.or(Some(err)),
+                    }
+                }
+            }
+            if !logger.propagate {
+                break;
+            }
+        }
+        match failure {
+            Some(err)   => Err(err),
+            None        => Ok(written),
+        }
+    }
+
+    /// Issues a record on the logger with the given name.
+    pub fn log(&self, logger: &str, level: Level, message: &str) -> io::Result<usize> {
+        self.dispatch(&Record::new(level, logger, message))
+    }
+}
+
+impl Default for LoggerTree {
+    fn default() -> Self {
+        LoggerTree::new()
+    }
+}
+
+/// Iterates over a logger name and the names of its ancestors, up to the root logger.
+fn ancestry(name: &str) -> impl Iterator<Item = &str> {
+    let mut next = Some(name);
+    std::iter::from_fn(move || {
+        let current = next?;
+        next = match current.rfind('.') {
+            Some(pos)                       => Some(&current[..pos]),
+            None if !current.is_empty()     => Some(""),
+            None                            => None,
+        };
+        Some(current)
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_logger_tree() {
+        let root = MemoryTarget::new();
+        let db = MemoryTarget::new();
+        let audit = MemoryTarget::new();
+
+        let mut tree = LoggerTree::new();
+        tree.logger("").level(Level::Warn).target(root.clone());
+        tree.logger("app.db").level(Level::Debug).target(db.clone()).target(audit.clone());
+        tree.logger("app.db.pool").filter(|record| !record.message.contains("idle"));
+        tree.logger("app.http").level(Level::Info).propagate(false);
+
+        assert_eq!(ancestry("app.db.pool").collect::<Vec<_>>(), vec!["app.db.pool", "app.db", "app", ""]);
+        assert_eq!(tree.effective_level("app.db.pool"), Level::Debug);
+        assert_eq!(tree.effective_level("app.cache"), Level::Warn);
+
+        // Fans out to both targets of `app.db`, the root ignores debug records.
+        assert_eq!(tree.log("app.db.pool", Level::Debug, "connection opened").unwrap(), 2);
+        // Handled by every level of the tree.
+        assert_eq!(tree.log("app.db", Level::Error, "deadlock").unwrap(), 3);
+        // Rejected by the filter of `app.db.pool`, but still handled by its ancestors.
+        assert_eq!(tree.log("app.db.pool", Level::Warn, "idle connections").unwrap(), 3);
+        // Not propagated to the root.
+        assert_eq!(tree.log("app.http", Level::Error, "bad gateway").unwrap(), 0);
+        assert_eq!(tree.log("app.cache", Level::Info, "miss").unwrap(), 0);
+
+        assert_eq!(db.lines(), vec!["DEBUG app.db.pool: connection opened", "ERROR app.db: deadlock",
+                                    "WARN  app.db.pool: idle connections"]);
+        assert_eq!(audit.records(), db.records());
+        assert_eq!(root.records().len(), 2);
+        assert_eq!(Level::from_name("warning"), Some(Level::Warn));
+    }
+
+    #[test]
+    fn test_logger_targets() {
+        let path = std::env::temp_dir().join(format!("patterns-logging-{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut tree = LoggerTree::new();
+        tree.logger("").target(FileTarget::open(&path).unwrap()).target(StderrTarget);
+        assert_eq!(tree.log("", Level::Info, "started").unwrap(), 2);
+        assert_eq!(tree.log("app", Level::Trace, "tick").unwrap(), 2);
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(content, "INFO  root: started\nTRACE app: tick\n");
+    }
+}
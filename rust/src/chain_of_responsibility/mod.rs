@@ -0,0 +1,260 @@
+//! Chain of Responsibility pattern.
+//!
+//! # Theory
+//! Behavioural design pattern consisting of command objects and a series of processing objects. This avoids coupling
+//! the sender of a request to its receiver by giving more than one object a chance to handle the request. Chain the
+//! receiving objects and pass the request along the chain until an object handles it.
+//!
+//! Each processing object contains logic that defines the types of command objects that it can handle. The rest are
+//! passed to the next processing object in the chain. In some sense, it is an object oriented version of a repeated
+//! `if` idiom, with the benefit that the condition-action blocks can be dynamically rearranged and reconfigured at
+//! runtime.
+//!
+//! # Participants
+//! - `PurchasePower`: the handler interface for handling requests. It may implement the successor link if it is
+//!   implemented using abstract classes.
+//! - `Employee`: a concrete handler handling requests it is responsible for. It can access its successor and forward
+//!   requests to it.
+//! - `Client`: the `tests` module. Initiates the request to a concrete handler object in the chain.
+//!
+//! # Modifications and Strategies
+//! Some processing objects can act as dispatchers, capable of sending commands out in a variety of directions, forming
+//! a _tree of responsibilities_. This is the case in typical logging architectures, as implemented in the `logging`
+//! module: records are passed from a logger up to its ancestors, and every logger on the way writes them to its
+//! targets.
+//!
+//! This is structurally nearly identical to the decorator pattern, the difference being that for decorators, all
+//! classes handle the request, while for the chain or responsibility, exactly one of the classes in the chain
+//! handles the request.
+//!
+//! Moreover, note that processing objects can have different types as long as they implement the correct interface. In
+//! the example below, a single type is used as the objects are extremely similar. On top of that, note that here
+//! concrete handlers own their successor. This is mainly done to enforce safe use. However, it is usually implemented
+//! in a way that handlers only hold a reference to their successors.
+//!
+//! The `handler` module generalises `PurchasePower` to any kind of request: a `Handler` does not know its successor,
+//! handlers are instead linked by a `Chain` assembled with a builder. Passing a request along a chain results in a
+//! typed `Outcome` rather than a sentence, and the request is handed back to the caller if no handler took care of it.
+//!
+//! As the chain is assembled at runtime, it can also be described in a configuration file (see the `config` module)
+//! and replaced while requests are being processed using a `ReloadableChain`.
+//!
+//! When several handlers must take part in a decision, such as co-signatures on large purchases, the request keeps
+//! travelling along the chain after it was handled. The `approval` module implements this, and records an audit trail
+//! of the decisions taken along the way.
+//!
+//! Handlers can also hold state deciding whether they take care of a request. In the `budget` module, approvers have
+//! a spending budget per period, and requests escalate to the successor once the budget of an approver is exhausted.
+//!
+//! Once handlers are linked, the `links` module allows to inspect and edit the chain: listing its members, inserting
+//! or removing roles, and previewing which handler would approve an amount without processing a request.
+//!
+//! # Example
+//! ```
+//! use patterns::chain_of_responsibility::{Chain, Employee, Outcome, PurchaseRequest};
+//!
+//! let chain = Chain::builder()
+//!     .then(Employee::new("manager", 5000))
+//!     .then(Employee::new("director", 10_000))
+//!     .build();
+//!
+//! match chain.process(PurchaseRequest::new(7500, "team lunch")) {
+//!     Outcome::Handled { by, response } => {
+//!         assert_eq!(by, "director");
+//!         assert_eq!(response, "director will approve $7500 for team lunch");
+//!     },
+//!     Outcome::Unhandled(_) => unreachable!(),
+//! }
+//! ```
+
+pub mod approval;
+pub mod budget;
+pub mod config;
+pub mod handler;
+pub mod links;
+pub mod logging;
+
+pub use self::handler::{Chain, ChainBuilder, Handler, Outcome, ReloadableChain};
+
+
+/// Trait to be implemented by all processing objects (handlers). Handlers must be thread-safe so that chains can be
+/// shared by the threads issuing requests.
+pub trait PurchasePower: Send + Sync {
+    /// Sets the handler to which unhandled requests are forwarded.
+    fn set_successor(&mut self, successor: Box<dyn PurchasePower>);
+    /// Returns the handler to which unhandled requests are forwarded, if any.
+    fn get_successor(&self) -> Option<&dyn PurchasePower>;
+    /// Unlinks the successor of the handler and returns it, if any. Handlers which do not override this method keep
+    /// their successor, hence a `PurchaseChain` cannot be edited past them.
+    fn take_successor(&mut self) -> Option<Box<dyn PurchasePower>> {
+        None
+    }
+    /// Whether the handler approves a request for the given amount itself, rather than forwarding it.
+    fn can_approve(&self, amount: u32) -> bool {
+        amount < self.get_allowable()
+    }
+    /// Handles the request or forwards it along the chain.
+    fn process_request(&self, request: PurchaseRequest) -> String {
+        if self.can_approve(request.get_amount()) {
+            approval(self.get_role(), &request)
+        } else {
+            match self.get_successor() {
+                Some(successor) => successor.process_request(request),
+                None            => String::from("Request amount is too high"),
+            }
+        }
+    }
+    /// The amount (exclusive) up to which this handler approves requests.
+    fn get_allowable(&self) -> u32;
+    /// The role of the handler.
+    fn get_role(&self) -> &str;
+
+}
+
+
+/// A concrete processing object (handler)
+pub struct Employee {
+    allowable: u32,
+    successor: Option<Box<dyn PurchasePower>>,
+    category: String,
+}
+impl Employee {
+    /// Creates an employee without a successor.
+    pub fn new<S>(category: S, allowable: u32) -> Employee where S: Into<String> {
+        Employee {
+            allowable,
+            successor: None,
+            category: category.into(),
+        }
+    }
+}
+impl PurchasePower for Employee {
+    fn set_successor(&mut self, successor: Box<dyn PurchasePower>) {
+        self.successor = Some(successor);
+    }
+
+    fn get_successor(&self) -> Option<&dyn PurchasePower> {
+        self.successor.as_deref()
+    }
+
+    fn take_successor(&mut self) -> Option<Box<dyn PurchasePower>> {
+        self.successor.take()
+    }
+
+    fn get_allowable(&self) -> u32 {
+        self.allowable
+    }
+
+    fn get_role(&self) -> &str {
+        &self.category
+    }
+}
+
+impl Handler<PurchaseRequest> for Employee {
+    type Response = String;
+
+    fn name(&self) -> &str {
+        &self.category
+    }
+
+    fn handle(&self, request: &PurchaseRequest) -> Option<String> {
+        if self.can_approve(request.get_amount()) {
+            Some(approval(&self.category, request))
+        } else {
+            None
+        }
+    }
+}
+
+/// Describes the approval of a request by some role.
+fn approval(role: &str, request: &PurchaseRequest) -> String {
+    format!("{} will approve ${} for {}", role, request.get_amount(), request.get_purpose())
+}
+
+/// Builds the usual approval hierarchy: a manager, a director, a vice-president and the president.
+pub fn management_chain() -> Chain<PurchaseRequest, String> {
+    Chain::builder()
+        .name("management")
+        .then(Employee::new("manager", 5000))
+        .then(Employee::new("director", 10_000))
+        .then(Employee::new("vice-president", 20_000))
+        .then(Employee::new("president", 40_000))
+        .build()
+}
+
+/// A request/command to be sent to processing objects
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PurchaseRequest {
+    amount: u32,
+    purpose: String,
+}
+impl PurchaseRequest {
+    /// Creates a request for some amount.
+    pub fn new<S>(amount: u32, purpose: S) -> PurchaseRequest where S: Into<String> {
+        PurchaseRequest {
+            amount,
+            purpose: purpose.into(),
+        }
+    }
+
+    /// The amount requested.
+    pub fn get_amount(&self) -> u32 {
+        self.amount
+    }
+
+    /// What the amount is requested for.
+    pub fn get_purpose(&self) -> &str {
+        self.purpose.as_ref()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chain_of_responsibility() {
+        let mut manager = Employee::new("manager", 5000);
+        let mut director = Employee::new("director", 10_000);
+        let mut vp = Employee::new("vice-president", 20_000);
+        let president = Employee::new("president", 40_000);
+
+        vp.set_successor(Box::new(president));
+        director.set_successor(Box::new(vp));
+        manager.set_successor(Box::new(director));
+
+        let request_0 = PurchaseRequest::new(12_000, "general expenses");
+        assert_eq!(manager.process_request(request_0), "vice-president will approve $12000 for general expenses");
+
+        let request_1 = PurchaseRequest::new(1_000_000, "buy a house");
+        assert_eq!(manager.process_request(request_1), "Request amount is too high");
+
+        let request_2 = PurchaseRequest::new(500, "desk repair");
+        assert_eq!(manager.process_request(request_2), "manager will approve $500 for desk repair");
+
+        let request_3 = PurchaseRequest::new(35_000, "company car");
+        assert_eq!(manager.process_request(request_3), "president will approve $35000 for company car");
+
+        let request_4 = PurchaseRequest::new(9000, "retreat event");
+        assert_eq!(manager.process_request(request_4), "director will approve $9000 for retreat event");
+    }
+
+    #[test]
+    fn test_chain_of_responsibility_handler() {
+        let chain = management_chain();
+
+        let outcome = chain.process(PurchaseRequest::new(12_000, "general expenses"));
+        assert_eq!(outcome, Outcome::Handled {
+            by: "vice-president".to_string(),
+            response: "vice-president will approve $12000 for general expenses".to_string(),
+        });
+
+        let request = PurchaseRequest::new(1_000_000, "buy a house");
+        assert_eq!(chain.process(request.clone()), Outcome::Unhandled(request));
+
+        assert_eq!(chain.process(PurchaseRequest::new(500, "desk repair")).handled_by(), Some("manager"));
+        assert_eq!(chain.process(PurchaseRequest::new(35_000, "company car")).handled_by(), Some("president"));
+        assert_eq!(chain.process(PurchaseRequest::new(9000, "retreat event")).handled_by(), Some("director"));
+    }
+}
@@ -0,0 +1,700 @@
+//! Scenarios run by the `patterns` binary.
+//!
+//! Every scenario takes the command line arguments following its subcommand and returns a `Report`: an ordered list of
+//! key/value pairs printed one per line. Invalid arguments or failing operations are reported as a `CliError`, which
+//! makes the binary exit with a non-zero status.
+
+use std::error::Error;
+use std::fmt;
+use std::result;
+use std::str::FromStr;
+
+use patterns::abstract_factory::{self, Debian, Factory, Factory2, Linux, OSX};
+use patterns::adapter::{Client, USBCharger};
+use patterns::bridge::{self, ColouredShape, DrawingBlue, DrawingRed};
+use patterns::builder::{Builder, CarBuilder, CarBuilderDirector};
+use patterns::catalogue::{Catalogue, Category, Pattern};
+use patterns::chain_of_responsibility::{self, Outcome, PurchaseRequest};
+use patterns::chain_of_responsibility::config::HierarchyConfig;
+use patterns::command::{CommandError, Switch};
+use patterns::composite::{CompositeGraphic, Ellipse, Graphic};
+use patterns::decorator::{self, Circle};
+use patterns::facade::Compiler;
+use patterns::factory_method::{CarFactory, SedanFactory};
+use patterns::flyweight::{CheeseShop, Menu};
+use patterns::iterator::CustomList;
+use patterns::mediator::{ButtonBook, ButtonSearch, ButtonView, Display, Mediator, ParticipantMediator};
+use patterns::observer::{Model, Observable, View};
+use patterns::proxy::{self, ICar, ProxyCar};
+use patterns::singleton;
+use patterns::state::{Post, TransitionError};
+use patterns::strategy::{FastAlgorithm, SlowAlgorithm, SomeObject};
+use patterns::template_method::Object;
+
+/// Result of a scenario.
+pub type Result<T> = result::Result<T, CliError>;
+
+/// Errors reported by the command line front end.
+#[derive(Debug, PartialEq)]
+pub enum CliError {
+    /// The arguments given to a subcommand are invalid.
+    Usage(String),
+    /// The scenario ran but one of its operations failed.
+    Failed(String),
+}
+
+impl CliError {
+    /// The exit status of the process when this error occurs.
+    pub fn exit_code(&self) -> i32 {
+        match *self {
+            CliError::Usage(_)  => 2,
+            CliError::Failed(_) => 1,
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CliError::Usage(ref msg)    => write!(f, "usage error: {}", msg),
+            CliError::Failed(ref msg)   => write!(f, "error: {}", msg),
+        }
+    }
+}
+
+impl Error for CliError {}
+
+/// The structured output of a scenario.
+#[derive(Debug, Default, PartialEq)]
+pub struct Report {
+    entries: Vec<(String, String)>,
+}
+
+impl Report {
+    /// Creates an empty report.
+    pub fn new() -> Report {
+        Report {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Appends an entry to the report.
+    pub fn add<K, V>(&mut self, key: K, value: V) -> &mut Report where K: Into<String>, V: ToString {
+        self.entries.push((key.into(), value.to_string()));
+        self
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (key, value) in &self.entries {
+            writeln!(f, "{}: {}", key, value)?;
+        }
+        Ok(())
+    }
+}
+
+/// A subcommand of the binary.
+pub struct Scenario {
+    /// The name of the subcommand.
+    pub name: &'static str,
+    /// The pattern illustrated by the scenario.
+    pub pattern: &'static str,
+    /// The arguments accepted by the subcommand.
+    pub usage: &'static str,
+    /// Runs the scenario on the arguments following the subcommand.
+    pub run: fn(&[String]) -> Result<Report>,
+}
+
+/// All scenarios, one per pattern.
+pub const SCENARIOS: &[Scenario] = &[
+    Scenario { name: "factory", pattern: "factory_method", usage: "", run: factory },
+    Scenario { name: "gui", pattern: "abstract_factory", usage: "<linux|osx|debian>", run: gui },
+    Scenario { name: "singleton", pattern: "singleton", usage: "<value>...", run: singleton },
+    Scenario { name: "graphic", pattern: "composite", usage: "<ellipses per group>...", run: graphic },
+    Scenario { name: "decorate", pattern: "decorator", usage: "--radius <radius> <colour>...", run: decorate },
+    Scenario { name: "car", pattern: "builder", usage: "[--colour <colour> --wheels <n> --seats <n>]", run: car },
+    Scenario { name: "drive", pattern: "proxy", usage: "--age <age>", run: drive },
+    Scenario { name: "switch", pattern: "command", usage: "<command [arguments]|UNDO|REDO|HELP>...", run: switch },
+    Scenario { name: "observe", pattern: "observer", usage: "--views <n> <value>...", run: observe },
+    Scenario { name: "post", pattern: "state", usage: "[--text <text>]", run: post },
+    Scenario { name: "algorithm", pattern: "strategy", usage: "<fast|slow>...", run: algorithm },
+    Scenario { name: "sort", pattern: "template_method", usage: "<name>=<weight>...", run: sort },
+    Scenario { name: "charge", pattern: "adapter", usage: "", run: charge },
+    Scenario { name: "draw", pattern: "bridge", usage: "<red|blue>", run: draw },
+    Scenario { name: "compile", pattern: "facade", usage: "", run: compile },
+    Scenario { name: "cheese", pattern: "flyweight", usage: "<sell <name> <qty>|stock <name> <cost> <qty>>...",
+               run: cheese },
+    Scenario { name: "chain", pattern: "chain_of_responsibility",
+               usage: "--amount <amount> [--purpose <purpose>] [--config <file>]", run: chain },
+    Scenario { name: "fibonacci", pattern: "iterator", usage: "[--count <n>]", run: fibonacci },
+    Scenario { name: "press", pattern: "mediator", usage: "<view|search|book>...", run: press },
+];
+
+/// Looks up a scenario by its subcommand name.
+pub fn find(name: &str) -> Option<&'static Scenario> {
+    SCENARIOS.iter().find(|scenario| scenario.name == name)
+}
+
+/// Returns the value following `--<name>` in the arguments, if present.
+fn option<'a>(args: &'a [String], name: &str) -> Result<Option<&'a str>> {
+    let flag = format!("--{}", name);
+    match args.iter().position(|arg| *arg == flag) {
+        Some(idx)   => match args.get(idx + 1) {
+            Some(value) => Ok(Some(value.as_str())),
+            None        => Err(CliError::Usage(format!("missing value for {}", flag))),
+        },
+        None        => Ok(None),
+    }
+}
+
+/// Flags that do not take a value.
+const SWITCHES: &[&str] = &["--json"];
+
+/// Returns whether the switch `--<name>` is present in the arguments.
+fn switch_on(args: &[String], name: &str) -> bool {
+    args.contains(&format!("--{}", name))
+}
+
+/// Returns the arguments that are neither flags nor flag values.
+fn positional(args: &[String]) -> Vec<&str> {
+    let mut result = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg.starts_with("--") {
+            if !SWITCHES.contains(&arg.as_str()) {
+                iter.next();
+            }
+        } else {
+            result.push(arg.as_str());
+        }
+    }
+    result
+}
+
+/// Parses a value, reporting which argument was invalid.
+fn parse<T>(value: &str, what: &str) -> Result<T> where T: FromStr {
+    value.parse().map_err(|_| CliError::Usage(format!("invalid {}: '{}'", what, value)))
+}
+
+/// Ensures at least one positional argument was given.
+fn require<'a>(values: Vec<&'a str>, what: &str) -> Result<Vec<&'a str>> {
+    if values.is_empty() {
+        return Err(CliError::Usage(format!("expected at least one {}", what)));
+    }
+    Ok(values)
+}
+
+fn factory(_args: &[String]) -> Result<Report> {
+    let mut report = Report::new();
+    report.add("car", SedanFactory.make_car().get_type());
+    Ok(report)
+}
+
+fn gui(args: &[String]) -> Result<Report> {
+    let system = match positional(args).first() {
+        Some(system)    => *system,
+        None            => return Err(CliError::Usage("expected a system".to_string())),
+    };
+    let (button, size) = match system {
+        "linux"     => (Linux.create_button().paint().to_string(), Linux.create_window().size()),
+        "osx"       => (OSX.create_button().paint().to_string(), OSX.create_window().size()),
+        "debian"    => {
+            let button = Factory2::create_button(&Debian);
+            let window = Factory2::create_window(&Debian);
+            (abstract_factory::Button::paint(&button).to_string(), abstract_factory::Window::size(&window))
+        },
+        other       => return Err(CliError::Usage(format!("unknown system: '{}'", other))),
+    };
+    let mut report = Report::new();
+    report.add("system", system).add("button", button).add("window", format!("{}x{}", size.0, size.1));
+    Ok(report)
+}
+
+fn singleton(args: &[String]) -> Result<Report> {
+    let mut report = Report::new();
+    for value in require(positional(args), "value")? {
+        *singleton::get_instance().lock() = parse(value, "value")?;
+        report.add("stored", value);
+    }
+    report.add("instance", *singleton::get_instance().lock());
+    Ok(report)
+}
+
+fn graphic(args: &[String]) -> Result<Report> {
+    let mut root = CompositeGraphic::new();
+    for count in require(positional(args), "group")? {
+        let mut group = CompositeGraphic::new();
+        for _ in 0..parse::<usize>(count, "group size")? {
+            group.add(Box::new(Ellipse));
+        }
+        root.add(Box::new(group));
+    }
+    let mut report = Report::new();
+    report.add("graphic", root.print());
+    Ok(report)
+}
+
+fn decorate(args: &[String]) -> Result<Report> {
+    let radius = match option(args, "radius")? {
+        Some(radius)    => parse(radius, "radius")?,
+        None            => 1_f32,
+    };
+    let mut shape: Box<dyn decorator::Shape> = Box::new(Circle { radius });
+    for colour in positional(args) {
+        shape = Box::new(decorator::ColouredShape::new(colour, shape));
+    }
+    let mut report = Report::new();
+    report.add("shape", shape.description());
+    Ok(report)
+}
+
+fn car(args: &[String]) -> Result<Report> {
+    let car = if args.is_empty() {
+        CarBuilderDirector::new().construct()
+    } else {
+        let mut builder = CarBuilder::new();
+        if let Some(colour) = option(args, "colour")? {
+            builder.set_colour(colour.to_string());
+        }
+        if let Some(wheels) = option(args, "wheels")? {
+            builder.set_wheels(parse(wheels, "wheel count")?);
+        }
+        if let Some(seats) = option(args, "seats")? {
+            builder.set_seats(parse(seats, "seat count")?);
+        }
+        builder.build()
+    };
+    let mut report = Report::new();
+    report.add("car", car.description());
+    Ok(report)
+}
+
+fn drive(args: &[String]) -> Result<Report> {
+    let age = match option(args, "age")? {
+        Some(age)   => parse(age, "age")?,
+        None        => return Err(CliError::Usage("missing --age".to_string())),
+    };
+    let car = proxy::Car;
+    let mut report = Report::new();
+    report.add("age", age).add("result", ProxyCar::new(age, &car).drive());
+    Ok(report)
+}
+
+fn switch(args: &[String]) -> Result<Report> {
+    let words = require(positional(args), "command")?;
+    let mut switch = Switch::new();
+
+    // A word naming a command starts a new command, the following words are its arguments.
+    let mut commands: Vec<String> = Vec::new();
+    for word in words {
+        let is_name = ["UNDO", "REDO", "HELP"].contains(&word) || switch.registry().get(word).is_some();
+        match commands.last_mut() {
+            Some(command) if !is_name   => {
+                command.push(' ');
+                command.push_str(word);
+            },
+            _                           => commands.push(word.to_string()),
+        }
+    }
+
+    let mut report = Report::new();
+    for command in &commands {
+        let result = match command.as_str() {
+            "UNDO"  => switch.undo().map_err(|err| CliError::Failed(err.to_string()))?.to_string(),
+            "REDO"  => switch.redo().map_err(|err| CliError::Failed(err.to_string()))?.to_string(),
+            "HELP"  => {
+                for spec in switch.registry().names().iter().filter_map(|name| switch.registry().get(name)) {
+                    report.add(spec.name(), spec);
+                }
+                continue;
+            },
+            _       => match switch.execute_command(command) {
+                Ok(outcome)                                         => outcome.to_string(),
+                Err(err @ CommandError::UnknownCommand(_))
+                | Err(err @ CommandError::InvalidArguments { .. })  => return Err(CliError::Usage(err.to_string())),
+                Err(err)                                            => return Err(CliError::Failed(err.to_string())),
+            },
+        };
+        report.add(command.as_str(), result);
+    }
+    report.add("light", switch.light());
+    Ok(report)
+}
+
+fn observe(args: &[String]) -> Result<Report> {
+    let count = match option(args, "views")? {
+        Some(count) => parse(count, "view count")?,
+        None        => 1,
+    };
+    let views: Vec<View> = (0..count).map(|idx| View::new(&format!("view_{}", idx))).collect();
+    let mut model = Model::new();
+    let _subscriptions: Vec<_> = views.iter().map(|view| model.subscribe(view.clone())).collect();
+    let mut report = Report::new();
+    for value in require(positional(args), "value")? {
+        if model.set_data(parse(value, "value")?) == 0 {
+            continue;
+        }
+        for line in views.iter().filter_map(|view| view.lines().pop()) {
+            report.add("update", line);
+        }
+    }
+    Ok(report)
+}
+
+fn post(args: &[String]) -> Result<Report> {
+    let text = option(args, "text")?.unwrap_or("I ate a salad for lunch today");
+    let mut post = Post::new();
+    let mut report = Report::new();
+    let failed = |err: TransitionError| CliError::Failed(err.to_string());
+    post.add_text(text).map_err(failed)?;
+    report.add(post.state(), format!("'{}'", post.content()));
+    post.request_review().map_err(failed)?;
+    report.add(post.state(), format!("'{}'", post.content()));
+    post.approve().map_err(failed)?;
+    report.add(post.state(), format!("'{}'", post.content()));
+    Ok(report)
+}
+
+fn algorithm(args: &[String]) -> Result<Report> {
+    let mut object = SomeObject::new(Box::new(FastAlgorithm));
+    let mut report = Report::new();
+    for name in require(positional(args), "algorithm")? {
+        match name {
+            "fast"  => object.set_behaviour(Box::new(FastAlgorithm)),
+            "slow"  => object.set_behaviour(Box::new(SlowAlgorithm)),
+            other   => return Err(CliError::Usage(format!("unknown algorithm: '{}'", other))),
+        }
+        report.add(name, object.run());
+    }
+    Ok(report)
+}
+
+fn sort(args: &[String]) -> Result<Report> {
+    let mut objects = Vec::new();
+    for item in require(positional(args), "object")? {
+        let mut parts = item.splitn(2, '=');
+        let name = parts.next().unwrap_or_default();
+        let weight = match parts.next() {
+            Some(weight)    => parse(weight, "weight")?,
+            None            => return Err(CliError::Usage(format!("expected <name>=<weight>, got '{}'", item))),
+        };
+        objects.push(Object::new(name, weight));
+    }
+    objects.sort();
+    let mut report = Report::new();
+    for (idx, object) in objects.iter().enumerate() {
+        report.add(idx.to_string(), object.print());
+    }
+    Ok(report)
+}
+
+fn charge(_args: &[String]) -> Result<Report> {
+    let mut report = Report::new();
+    report.add("result", Client::do_stuff_with_usb(&USBCharger::new()));
+    Ok(report)
+}
+
+fn draw(args: &[String]) -> Result<Report> {
+    let drawing = match positional(args).first() {
+        Some(&"red")    => bridge::Shape::draw(&<ColouredShape<DrawingRed> as bridge::Shape<_>>::new(DrawingRed)),
+        Some(&"blue")   => bridge::Shape::draw(&<ColouredShape<DrawingBlue> as bridge::Shape<_>>::new(DrawingBlue)),
+        Some(other)     => return Err(CliError::Usage(format!("unknown colour: '{}'", other))),
+        None            => return Err(CliError::Usage("expected a colour".to_string())),
+    };
+    let mut report = Report::new();
+    report.add("result", drawing);
+    Ok(report)
+}
+
+fn compile(_args: &[String]) -> Result<Report> {
+    let mut report = Report::new();
+    for step in Compiler::new().run().lines() {
+        report.add("step", step);
+    }
+    Ok(report)
+}
+
+fn cheese(args: &[String]) -> Result<Report> {
+    let menu = Menu::new();
+    let mut shop = CheeseShop::new(&menu);
+    shop.stock_cheese("blue", 2.5, 10_f32);
+    shop.stock_cheese("white", 1.25, 20_f32);
+
+    let mut report = Report::new();
+    let mut ops = require(positional(args), "operation")?.into_iter();
+    while let Some(op) = ops.next() {
+        let mut next = |what: &str| ops.next().ok_or_else(|| CliError::Usage(format!("{} expects a {}", op, what)));
+        match op {
+            "sell"  => {
+                let name = next("name")?;
+                let quantity = parse(next("quantity")?, "quantity")?;
+                shop.sell(name, quantity)
+                    .map_err(|err| CliError::Failed(format!("cannot sell {} {}: {}", quantity, name, err)))?;
+                report.add("sold", format!("{} {}", quantity, name));
+            },
+            "stock" => {
+                let name = next("name")?;
+                let cost = parse(next("cost")?, "cost")?;
+                let quantity: f32 = parse(next("quantity")?, "quantity")?;
+                shop.stock_cheese(name, cost, quantity);
+                report.add("stocked", format!("{} {} at {}", quantity, name, cost));
+            },
+            other   => return Err(CliError::Usage(format!("unknown operation: '{}'", other))),
+        }
+    }
+    report.add("units sold", shop.total_units_sold()).add("revenue", shop.total_revenue());
+    Ok(report)
+}
+
+fn chain(args: &[String]) -> Result<Report> {
+    let amount = match option(args, "amount")? {
+        Some(amount)    => parse(amount, "amount")?,
+        None            => return Err(CliError::Usage("missing --amount".to_string())),
+    };
+    let purpose = option(args, "purpose")?.unwrap_or("unspecified");
+
+    let chain = match option(args, "config")? {
+        Some(path)  => HierarchyConfig::load(path).map_err(|err| CliError::Failed(err.to_string()))?.build(),
+        None        => chain_of_responsibility::management_chain(),
+    };
+    match chain.process(PurchaseRequest::new(amount, purpose)) {
+        Outcome::Handled { by, response }   => {
+            let mut report = Report::new();
+            report.add("amount", amount).add("purpose", purpose).add("handled by", by).add("result", response);
+            Ok(report)
+        },
+        Outcome::Unhandled(request)         => Err(CliError::Failed(format!(
+            "request amount is too high: nobody can approve ${} for {}", request.get_amount(), request.get_purpose()))),
+    }
+}
+
+fn fibonacci(args: &[String]) -> Result<Report> {
+    let count = match option(args, "count")? {
+        Some(count) => parse(count, "count")?,
+        None        => 10,
+    };
+    let numbers: Vec<String> = CustomList::new("fibonacci").into_iter().take(count).map(|n| n.to_string()).collect();
+    let mut report = Report::new();
+    report.add("sequence", numbers.join(" "));
+    Ok(report)
+}
+
+fn press(args: &[String]) -> Result<Report> {
+    let (view, search, book, display) = (ButtonView::new(), ButtonSearch::new(), ButtonBook::new(), Display);
+    let mut mediator = ParticipantMediator::new();
+    mediator.register_view(&view);
+    mediator.register_search(&search);
+    mediator.register_book(&book);
+    mediator.register_display(&display);
+
+    let mut report = Report::new();
+    for button in require(positional(args), "button")? {
+        let display = match button {
+            "view"      => mediator.view(),
+            "search"    => mediator.search(),
+            "book"      => mediator.book(),
+            other       => return Err(CliError::Usage(format!("unknown button: '{}'", other))),
+        };
+        report.add(button, display);
+    }
+    let (views, searches, bookings) = mediator.get_counts();
+    report.add("counts", format!("view={} search={} book={}", views, searches, bookings));
+    Ok(report)
+}
+
+/// Lists the patterns of the catalogue: `list [--category <category>] [--json]`.
+pub fn list(args: &[String]) -> Result<String> {
+    let catalogue = Catalogue::new();
+    let patterns: Vec<&Pattern> = match option(args, "category")? {
+        Some(name)  => match Category::from_name(name) {
+            Some(category)  => catalogue.by_category(category),
+            None            => return Err(CliError::Usage(format!("unknown category: '{}'", name))),
+        },
+        None        => catalogue.patterns().iter().collect(),
+    };
+
+    if switch_on(args, "json") {
+        let entries: Vec<String> = patterns.iter().map(|pattern| {
+            format!("{{\"name\":{},\"title\":{},\"category\":{}}}",
+                    json_string(pattern.name), json_string(pattern.title), json_string(pattern.category.as_str()))
+        }).collect();
+        return Ok(format!("[{}]\n", entries.join(",")));
+    }
+    let mut output = String::new();
+    for pattern in patterns {
+        output.push_str(&format!("{:<24} {:<12} {}\n", pattern.name, pattern.category, pattern.title));
+    }
+    Ok(output)
+}
+
+/// Shows the documentation of a single pattern: `show <name> [--json]`.
+pub fn show(args: &[String]) -> Result<String> {
+    let catalogue = Catalogue::new();
+    let name = match positional(args).first() {
+        Some(name)  => *name,
+        None        => return Err(CliError::Usage("expected a pattern name".to_string())),
+    };
+    let pattern = match catalogue.get(name) {
+        Some(pattern)   => pattern,
+        None            => return Err(CliError::Failed(format!("unknown pattern: '{}'", name))),
+    };
+    if switch_on(args, "json") {
+        Ok(format!("{}\n", pattern_json(pattern)))
+    } else {
+        Ok(pattern_text(pattern))
+    }
+}
+
+/// Renders a pattern as plain text.
+fn pattern_text(pattern: &Pattern) -> String {
+    let mut output = format!("{} ({})\ncategory: {}\n\n# Theory\n{}\n\n# Participants\n",
+                             pattern.title, pattern.name, pattern.category, pattern.theory);
+    for participant in &pattern.participants {
+        match participant.types.len() {
+            0   => output.push_str(&format!("- {}\n", participant.description)),
+            _   => output.push_str(&format!("- {}: {}\n", participant.types.join(", "), participant.description)),
+        }
+    }
+    if let Some(ref strategies) = pattern.strategies {
+        output.push_str(&format!("\n# Modifications and Strategies\n{}\n", strategies));
+    }
+    if let Some(ref attention) = pattern.attention {
+        output.push_str(&format!("\n# Attention\n{}\n", attention));
+    }
+    if !pattern.known_uses.is_empty() {
+        output.push_str("\n# Known Uses\n");
+        for known_use in &pattern.known_uses {
+            output.push_str(&format!("- {}\n", known_use));
+        }
+    }
+    for (heading, text) in &pattern.notes {
+        output.push_str(&format!("\n# {}\n{}\n", heading, text));
+    }
+    output
+}
+
+/// Renders a pattern as a JSON object.
+fn pattern_json(pattern: &Pattern) -> String {
+    let optional = |value: &Option<String>| value.as_ref().map_or("null".to_string(), |value| json_string(value));
+    let strings = |values: &[String]| {
+        let values: Vec<String> = values.iter().map(|value| json_string(value)).collect();
+        format!("[{}]", values.join(","))
+    };
+    let participants: Vec<String> = pattern.participants.iter().map(|participant| {
+        format!("{{\"types\":{},\"description\":{}}}",
+                strings(&participant.types), json_string(&participant.description))
+    }).collect();
+    let notes: Vec<String> = pattern.notes.iter().map(|(heading, text)| {
+        format!("{{\"heading\":{},\"text\":{}}}", json_string(heading), json_string(text))
+    }).collect();
+    format!("{{\"name\":{},\"title\":{},\"category\":{},\"theory\":{},\"participants\":[{}],\"strategies\":{},\
+             \"attention\":{},\"known_uses\":{},\"notes\":[{}]}}",
+            json_string(pattern.name), json_string(pattern.title), json_string(pattern.category.as_str()),
+            json_string(&pattern.theory), participants.join(","), optional(&pattern.strategies),
+            optional(&pattern.attention), strings(&pattern.known_uses), notes.join(","))
+}
+
+/// Encodes a string as a JSON string literal.
+fn json_string(value: &str) -> String {
+    let mut result = String::with_capacity(value.len() + 2);
+    result.push('"');
+    for c in value.chars() {
+        match c {
+            '"'                         => result.push_str("\\\""),
+            '\\'                       => result.push_str("\\\\"),
+            '\n'                        => result.push_str("\\n"),
+            '\r'                        => result.push_str("\\r"),
+            '\t'                        => result.push_str("\\t"),
+            c if (c as u32) < 0x20      => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c                           => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    fn run(name: &str, values: &[&str]) -> Result<Report> {
+        (find(name).expect("unknown scenario").run)(&args(values))
+    }
+
+    #[test]
+    fn test_cli() {
+        let report = run("chain", &["--amount", "12000", "--purpose", "general expenses"]).unwrap();
+        assert_eq!(report.to_string(),
+                   "amount: 12000\npurpose: general expenses\nhandled by: vice-president\n\
+                    result: vice-president will approve $12000 for general expenses\n");
+
+        let report = run("switch", &["ON", "OFF", "UNDO", "REDO", "TOGGLE", "DIM", "40"]).unwrap();
+        assert_eq!(report.to_string(), "ON: light turned on\nOFF: light turned off\nUNDO: light restored: on at 100%\n\
+                                        REDO: light turned off\nTOGGLE: light turned on\nDIM 40: light dimmed to 40%\n\
+                                        light: on at 40%\n");
+        let report = run("switch", &["HELP"]).unwrap();
+        assert!(report.to_string().starts_with("ON: ON: turns the light on\n"));
+
+        let report = run("cheese", &["sell", "blue", "5"]).unwrap();
+        assert_eq!(report.to_string(), "sold: 5 blue\nunits sold: 5\nrevenue: 12.5\n");
+
+        let report = run("post", &[]).unwrap();
+        assert_eq!(report.to_string(),
+                   "Draft: ''\nPendingReview: ''\nPublished: 'I ate a salad for lunch today'\n");
+
+        for scenario in SCENARIOS.iter().filter(|scenario| scenario.usage.is_empty()) {
+            assert!((scenario.run)(&[]).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_cli_errors() {
+        assert_eq!(run("switch", &["BLINK", "ON"]), Err(CliError::Usage("unknown command: 'BLINK'".to_string())));
+        assert_eq!(run("switch", &["ON", "BLINK"]),
+                   Err(CliError::Usage("invalid arguments for ON: no arguments expected".to_string())));
+        assert_eq!(run("switch", &["ON", "DIM"]),
+                   Err(CliError::Usage("invalid arguments for DIM: missing brightness".to_string())));
+        assert_eq!(run("switch", &["DIM", "40"]),
+                   Err(CliError::Failed("cannot dim a light which is off".to_string())));
+        assert_eq!(run("switch", &["ON", "UNDO", "UNDO"]), Err(CliError::Failed("nothing to undo".to_string())));
+        assert_eq!(run("switch", &["REDO"]), Err(CliError::Failed("nothing to redo".to_string())));
+        assert_eq!(run("chain", &["--amount"]), Err(CliError::Usage("missing value for --amount".to_string())));
+        assert_eq!(run("chain", &["--amount", "lots"]), Err(CliError::Usage("invalid amount: 'lots'".to_string())));
+        assert_eq!(run("chain", &["--amount", "50000", "--purpose", "boat"]),
+                   Err(CliError::Failed("request amount is too high: nobody can approve $50000 for boat".to_string())));
+        let err = run("chain", &["--amount", "5", "--config", "/nonexistent.toml"]).unwrap_err();
+        assert_eq!(err.to_string(), "error: cannot read /nonexistent.toml: No such file or directory (os error 2)");
+
+        let err = run("cheese", &["sell", "blue", "50"]).unwrap_err();
+        assert_eq!(err, CliError::Failed("cannot sell 50 blue: Out of stock".to_string()));
+        assert_eq!(err.exit_code(), 1);
+    }
+
+    #[test]
+    fn test_cli_catalogue() {
+        let output = list(&args(&["--category", "creational"])).unwrap();
+        assert_eq!(output.lines().count(), 5);
+        assert!(output.starts_with("factory_method           creational   Factory Method\n"));
+        assert_eq!(list(&args(&["--category", "functional"])),
+                   Err(CliError::Usage("unknown category: 'functional'".to_string())));
+
+        let output = list(&args(&["--json", "--category", "structural"])).unwrap();
+        assert!(output.starts_with("[{\"name\":\"composite\",\"title\":\"Composite\",\"category\":\"structural\"},"));
+
+        let output = show(&args(&["--json", "state"])).unwrap();
+        assert!(output.contains("\"notes\":[{\"heading\":\"Note\",\"text\":\"The following code is taken from the"));
+        assert!(output.contains("\"participants\":[{\"types\":[\"Post\"],\"description\":\"the context"));
+
+        let output = show(&args(&["chain_of_responsibility"])).unwrap();
+        assert!(output.starts_with("Chain of Responsibility (chain_of_responsibility)\ncategory: behavioural\n"));
+        assert!(output.contains("\n- PurchasePower: the handler interface for handling requests."));
+        assert_eq!(show(&args(&["visitor"])), Err(CliError::Failed("unknown pattern: 'visitor'".to_string())));
+
+        assert_eq!(json_string("a \"quote\"\n\\"), "\"a \\\"quote\\\"\\n\\\\\"");
+    }
+}
@@ -0,0 +1,55 @@
+//! Sources of the current time.
+//!
+//! Patterns acting on time, such as spending budgets, scheduled commands, grouped edits or time based operators on
+//! streams, read it from a `Clock` rather than from the system. The `SystemClock` tells the time of the operating
+//! system, whereas a `ManualClock` only moves when told to, which makes these patterns deterministic in tests.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The source of the current time.
+pub trait Clock: Send + Sync {
+    /// The time elapsed since the origin of the clock, which is the UNIX epoch for the system clock.
+    fn now(&self) -> Duration;
+}
+
+/// The clock of the operating system.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default()
+    }
+}
+
+/// A clock that only moves when told to. Clones share the same time.
+#[derive(Clone, Debug, Default)]
+pub struct ManualClock {
+    now: Arc<Mutex<Duration>>,
+}
+
+impl ManualClock {
+    /// Creates a clock showing the given time.
+    pub fn new(now: Duration) -> ManualClock {
+        ManualClock {
+            now: Arc::new(Mutex::new(now)),
+        }
+    }
+
+    /// Moves the clock forward.
+    pub fn advance(&self, by: Duration) {
+        *self.now.lock().unwrap() += by;
+    }
+
+    /// Sets the time shown by the clock.
+    pub fn set(&self, now: Duration) {
+        *self.now.lock().unwrap() = now;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        *self.now.lock().unwrap()
+    }
+}
@@ -0,0 +1,543 @@
+//! Grouped undo for a text editor.
+//!
+//! A `TextBuffer` is the receiver of `InsertCommand` and `DeleteCommand`. Undoing every key press on its own is
+//! tedious, so the `UndoManager` gathers consecutive edits in groups, which are undone and redone as units.
+//!
+//! Edits continue the last group when they carry on where the previous edit stopped: typing right after the previous
+//! insertion, or deleting right before (backspace) or at (delete) the previous deletion. The `Grouping` then decides
+//! when a group ends:
+//! - `Keystroke`: never, every edit is its own group.
+//! - `Word`: after inserting whitespace, such that every word is undone with the space following it.
+//! - `Window`: once more time than the window has passed since the previous edit.
+//!
+//! Edits made between `begin_group()` and `end_group()` always form a single group, whatever the grouping. The
+//! manager reads the time from a `Clock`, which is the system clock unless another one is given with `clock()`.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+use std::time::Duration;
+
+use super::{macro_command, Command, CommandError, Result};
+use crate::clock::{Clock, SystemClock};
+
+/// What a command did to a text buffer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EditOutcome {
+    /// Text was inserted.
+    Inserted {
+        /// The position of the first character inserted.
+        position: usize,
+        /// The text inserted.
+        text: String,
+    },
+    /// Text was deleted.
+    Deleted {
+        /// The position of the first character deleted.
+        position: usize,
+        /// The text deleted.
+        text: String,
+    },
+    /// A group of edits was undone. It holds the names of the edits, in the order in which they were undone.
+    GroupUndone(Vec<String>),
+    /// A group of edits was redone. It holds the names of the edits, in the order in which they were redone.
+    GroupRedone(Vec<String>),
+}
+
+impl fmt::Display for EditOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EditOutcome::Inserted { position, ref text }    => write!(f, "inserted '{}' at {}", text, position),
+            EditOutcome::Deleted { position, ref text }     => write!(f, "deleted '{}' at {}", text, position),
+            EditOutcome::GroupUndone(ref steps)             => write!(f, "undone: {}", macro_command::list(steps)),
+            EditOutcome::GroupRedone(ref steps)             => write!(f, "redone: {}", macro_command::list(steps)),
+        }
+    }
+}
+
+/// The position `count` characters after the given one, failing if it cannot even be represented.
+fn end(position: usize, count: usize) -> Result<usize> {
+    position.checked_add(count).ok_or_else(|| {
+        CommandError::Failed(format!("{} characters from position {} are beyond the end of the text", count, position))
+    })
+}
+
+/// Text edited by commands. Positions and lengths are counted in characters.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TextBuffer {
+    text: String,
+}
+
+/// A text buffer shared between the commands acting on it.
+pub type SharedBuffer = Rc<RefCell<TextBuffer>>;
+
+impl TextBuffer {
+    /// Creates an empty buffer.
+    pub fn new() -> TextBuffer {
+        TextBuffer::default()
+    }
+
+    /// The text of the buffer.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The number of characters in the buffer.
+    pub fn len(&self) -> usize {
+        self.text.chars().count()
+    }
+
+    /// Whether the buffer is empty.
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Inserts text before the character at the given position.
+    pub fn insert(&mut self, position: usize, text: &str) -> Result<EditOutcome> {
+        let index = self.index(position)?;
+        self.text.insert_str(index, text);
+        Ok(EditOutcome::Inserted { position, text: text.to_string() })
+    }
+
+    /// Deletes `count` characters from the given position.
+    pub fn delete(&mut self, position: usize, count: usize) -> Result<EditOutcome> {
+        let start = self.index(position)?;
+        let end = self.index(end(position, count)?)?;
+        let text = self.text.drain(start..end).collect();
+        Ok(EditOutcome::Deleted { position, text })
+    }
+
+    /// The byte index of a character position.
+    fn index(&self, position: usize) -> Result<usize> {
+        self.text.char_indices().map(|(index, _)| index).chain(Some(self.text.len())).nth(position)
+            .ok_or_else(|| CommandError::Failed(format!("position {} is beyond the end of the text", position)))
+    }
+}
+
+impl fmt::Display for TextBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(&self.text)
+    }
+}
+
+/// A command inserting text.
+pub struct InsertCommand {
+    buffer: SharedBuffer,
+    position: usize,
+    text: String,
+}
+
+impl InsertCommand {
+    /// Creates a command inserting text at the given position.
+    pub fn new<S>(buffer: SharedBuffer, position: usize, text: S) -> InsertCommand where S: Into<String> {
+        InsertCommand {
+            buffer,
+            position,
+            text: text.into(),
+        }
+    }
+}
+
+impl Command<EditOutcome> for InsertCommand {
+    fn name(&self) -> String {
+        format!("insert '{}' at {}", self.text, self.position)
+    }
+
+    fn execute(&mut self) -> Result<EditOutcome> {
+        self.buffer.borrow_mut().insert(self.position, &self.text)
+    }
+
+    fn unexecute(&mut self) -> Result<EditOutcome> {
+        self.buffer.borrow_mut().delete(self.position, self.text.chars().count())
+    }
+}
+
+/// A command deleting text.
+pub struct DeleteCommand {
+    buffer: SharedBuffer,
+    position: usize,
+    count: usize,
+    deleted: Option<String>,
+}
+
+impl DeleteCommand {
+    /// Creates a command deleting `count` characters from the given position.
+    pub fn new(buffer: SharedBuffer, position: usize, count: usize) -> DeleteCommand {
+        DeleteCommand {
+            buffer,
+            position,
+            count,
+            deleted: None,
+        }
+    }
+}
+
+impl Command<EditOutcome> for DeleteCommand {
+    fn name(&self) -> String {
+        format!("delete {} at {}", self.count, self.position)
+    }
+
+    fn execute(&mut self) -> Result<EditOutcome> {
+        let outcome = self.buffer.borrow_mut().delete(self.position, self.count)?;
+        if let EditOutcome::Deleted { ref text, .. } = outcome {
+            self.deleted = Some(text.clone());
+        }
+        Ok(outcome)
+    }
+
+    fn unexecute(&mut self) -> Result<EditOutcome> {
+        let deleted = self.deleted.take().ok_or(CommandError::NothingToUndo)?;
+        self.buffer.borrow_mut().insert(self.position, &deleted)
+    }
+}
+
+/// How consecutive edits are gathered in groups.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Grouping {
+    /// Every edit is a group.
+    Keystroke,
+    /// A group ends after whitespace is inserted.
+    Word,
+    /// A group ends when no edit was made for longer than the window.
+    Window(Duration),
+}
+
+/// Where the last edit of a group stopped, to tell whether the next edit carries on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Edit {
+    Insert { end: usize },
+    Delete { position: usize },
+}
+
+impl Edit {
+    fn continued_by(&self, next: &Edit, count: usize) -> bool {
+        match (*self, *next) {
+            (Edit::Insert { end }, Edit::Insert { end: next })                  => next == end + count,
+            (Edit::Delete { position }, Edit::Delete { position: next })        => {
+                next == position || next + count == position
+            },
+            _                                                                   => false,
+        }
+    }
+}
+
+/// Commands undone and redone together.
+struct Group {
+    commands: Vec<Box<dyn Command<EditOutcome>>>,
+    last: Option<Edit>,
+    at: Duration,
+}
+
+impl Group {
+    fn names(&self) -> Vec<String> {
+        self.commands.iter().map(|command| command.name()).collect()
+    }
+}
+
+/// An invoker undoing and redoing edits of a text buffer in groups.
+pub struct UndoManager {
+    buffer: SharedBuffer,
+    grouping: Grouping,
+    clock: Box<dyn Clock>,
+    history: Vec<Group>,
+    undone: Vec<Group>,
+    open: bool,
+    depth: usize,
+}
+
+impl UndoManager {
+    /// Creates a manager editing an empty buffer.
+    pub fn new(grouping: Grouping) -> UndoManager {
+        UndoManager {
+            buffer: SharedBuffer::default(),
+            grouping,
+            clock: Box::new(SystemClock),
+            history: Vec::new(),
+            undone: Vec::new(),
+            open: false,
+            depth: 0,
+        }
+    }
+
+    /// The text being edited.
+    pub fn text(&self) -> String {
+        self.buffer.borrow().text().to_string()
+    }
+
+    /// The buffer edited, to create commands acting on it.
+    pub fn receiver(&self) -> SharedBuffer {
+        Rc::clone(&self.buffer)
+    }
+
+    /// Sets the clock telling when edits are made.
+    pub fn clock<C>(mut self, clock: C) -> Self where C: Clock + 'static {
+        self.clock = Box::new(clock);
+        self
+    }
+
+    /// Inserts text, which may continue the last group.
+    pub fn insert(&mut self, position: usize, text: &str) -> Result<EditOutcome> {
+        let count = text.chars().count();
+        let command = InsertCommand::new(self.receiver(), position, text);
+        let outcome = self.apply(Box::new(command), Some(Edit::Insert { end: end(position, count)? }), count)?;
+        if self.grouping == Grouping::Word && text.ends_with(char::is_whitespace) {
+            self.open = false;
+        }
+        Ok(outcome)
+    }
+
+    /// Deletes `count` characters, which may continue the last group.
+    pub fn delete(&mut self, position: usize, count: usize) -> Result<EditOutcome> {
+        let command = DeleteCommand::new(self.receiver(), position, count);
+        self.apply(Box::new(command), Some(Edit::Delete { position }), count)
+    }
+
+    /// Executes any other command. It starts a group of its own unless a group was begun explicitly.
+    pub fn execute(&mut self, command: Box<dyn Command<EditOutcome>>) -> Result<EditOutcome> {
+        self.apply(command, None, 0)
+    }
+
+    /// Starts a group containing all edits until the matching `end_group()`. Groups can be nested, in which case the
+    /// outermost one contains all edits.
+    pub fn begin_group(&mut self) {
+        if self.depth == 0 {
+            self.undone.clear();
+            self.history.push(Group { commands: Vec::new(), last: None, at: self.clock.now() });
+        }
+        self.depth += 1;
+    }
+
+    /// Ends a group started by `begin_group()`. A group without edits is forgotten.
+    ///
+    /// # Panics
+    /// Panics if no group was begun.
+    pub fn end_group(&mut self) {
+        assert!(self.depth > 0, "end_group() called without begin_group()");
+        self.depth -= 1;
+        if self.depth == 0 {
+            self.open = false;
+            if self.history.last().is_some_and(|group| group.commands.is_empty()) {
+                self.history.pop();
+            }
+        }
+    }
+
+    /// Undoes the last group. Any group begun explicitly is ended first.
+    pub fn undo(&mut self) -> Result<EditOutcome> {
+        self.close();
+        let mut group = self.history.pop().ok_or(CommandError::NothingToUndo)?;
+        for (idx, command) in group.commands.iter_mut().enumerate().rev() {
+            if let Err(err) = command.unexecute() {
+                // The group goes back to the history even if it cannot be restored, in which case the error restoring
+                // it is reported.
+                let restored = group.commands[idx + 1..].iter_mut().try_for_each(|command| {
+                    command.execute().map(|_| ())
+                });
+                self.history.push(group);
+                return Err(restored.err().unwrap_or(err));
+            }
+        }
+        let steps = group.names().into_iter().rev().collect();
+        self.undone.push(group);
+        Ok(EditOutcome::GroupUndone(steps))
+    }
+
+    /// Redoes the last group that was undone.
+    pub fn redo(&mut self) -> Result<EditOutcome> {
+        self.close();
+        let mut group = self.undone.pop().ok_or(CommandError::NothingToRedo)?;
+        for (idx, command) in group.commands.iter_mut().enumerate() {
+            if let Err(err) = command.execute() {
+                let restored = group.commands[..idx].iter_mut().rev().try_for_each(|command| {
+                    command.unexecute().map(|_| ())
+                });
+                self.undone.push(group);
+                return Err(restored.err().unwrap_or(err));
+            }
+        }
+        let steps = group.names();
+        self.history.push(group);
+        Ok(EditOutcome::GroupRedone(steps))
+    }
+
+    /// The number of groups that can be undone.
+    pub fn undo_len(&self) -> usize {
+        self.history.len()
+    }
+
+    /// The number of groups that can be redone.
+    pub fn redo_len(&self) -> usize {
+        self.undone.len()
+    }
+
+    /// Executes a command and adds it to the last group or to a new one.
+    fn apply(&mut self, mut command: Box<dyn Command<EditOutcome>>, edit: Option<Edit>, count: usize)
+             -> Result<EditOutcome> {
+        let outcome = command.execute()?;
+        self.undone.clear();
+        let now = self.clock.now();
+        let continues = self.depth > 0 || (self.open && self.history.last().is_some_and(|group| {
+            let within = match self.grouping {
+                Grouping::Keystroke         => false,
+                Grouping::Word              => true,
+                // The system clock may go back, in which case the edits are taken as simultaneous.
+                Grouping::Window(window)    => now.saturating_sub(group.at) <= window,
+            };
+            within && matches!((group.last, edit), (Some(last), Some(ref edit)) if last.continued_by(edit, count))
+        }));
+        if !continues {
+            self.history.push(Group { commands: Vec::new(), last: None, at: now });
+        }
+        let group = self.history.last_mut().unwrap();
+        group.commands.push(command);
+        group.last = edit;
+        group.at = now;
+        self.open = edit.is_some();
+        Ok(outcome)
+    }
+
+    /// Ends the current group, explicit or not.
+    fn close(&mut self) {
+        while self.depth > 0 {
+            self.end_group();
+        }
+        self.open = false;
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::cell::Cell;
+
+    use crate::clock::ManualClock;
+
+    /// A command failing once the calls it was given have run out.
+    struct Flaky {
+        calls: Rc<Cell<u32>>,
+    }
+
+    impl Flaky {
+        fn call(&self) -> Result<EditOutcome> {
+            match self.calls.get() {
+                0       => Err(CommandError::Failed("out of calls".to_string())),
+                calls   => {
+                    self.calls.set(calls - 1);
+                    Ok(EditOutcome::GroupRedone(Vec::new()))
+                },
+            }
+        }
+    }
+
+    impl Command<EditOutcome> for Flaky {
+        fn name(&self) -> String {
+            "flaky".to_string()
+        }
+
+        fn execute(&mut self) -> Result<EditOutcome> {
+            self.call()
+        }
+
+        fn unexecute(&mut self) -> Result<EditOutcome> {
+            self.call()
+        }
+    }
+
+    fn type_text(manager: &mut UndoManager, position: usize, text: &str) {
+        for (idx, c) in text.chars().enumerate() {
+            manager.insert(position + idx, &c.to_string()).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_word_grouping() {
+        let mut manager = UndoManager::new(Grouping::Word);
+        type_text(&mut manager, 0, "hello wrld");
+        assert_eq!(manager.undo_len(), 2);
+
+        // Backspaces form a single group.
+        manager.delete(9, 1).unwrap();
+        manager.delete(8, 1).unwrap();
+        manager.delete(7, 1).unwrap();
+        type_text(&mut manager, 7, "orld");
+        assert_eq!(manager.text(), "hello world");
+        assert_eq!(manager.undo_len(), 4);
+
+        manager.undo().unwrap();
+        assert_eq!(manager.undo().unwrap().to_string(), "undone: delete 1 at 7, delete 1 at 8, delete 1 at 9");
+        assert_eq!(manager.text(), "hello wrld");
+        manager.undo().unwrap();
+        assert_eq!(manager.text(), "hello ");
+        assert_eq!(manager.redo_len(), 3);
+
+        manager.redo().unwrap();
+        assert_eq!(manager.text(), "hello wrld");
+        // A new edit discards the groups that were undone, and does not continue a group that was redone.
+        manager.insert(0, "Oh, ").unwrap();
+        assert_eq!(manager.redo(), Err(CommandError::NothingToRedo));
+        assert_eq!(manager.undo().unwrap(), EditOutcome::GroupUndone(vec!["insert 'Oh, ' at 0".to_string()]));
+        assert!(manager.insert(42, "!").is_err());
+        assert!(manager.insert(usize::MAX, "!").is_err());
+        assert!(manager.delete(1, usize::MAX).is_err());
+        assert_eq!(manager.receiver().borrow_mut().delete(1, usize::MAX).unwrap_err().to_string(),
+                   format!("{} characters from position 1 are beyond the end of the text", usize::MAX));
+        assert_eq!(manager.undo_len(), 2);
+    }
+
+    #[test]
+    fn test_window_and_explicit_grouping() {
+        let clock = ManualClock::default();
+        let mut manager = UndoManager::new(Grouping::Window(Duration::from_millis(500))).clock(clock.clone());
+        type_text(&mut manager, 0, "ab");
+        clock.advance(Duration::from_millis(400));
+        type_text(&mut manager, 2, "cd");
+        clock.advance(Duration::from_secs(1));
+        type_text(&mut manager, 4, "ef");
+        assert_eq!(manager.undo_len(), 2);
+        manager.undo().unwrap();
+        assert_eq!(manager.text(), "abcd");
+
+        // Edits at another place start a new group, unless grouped explicitly.
+        manager.begin_group();
+        manager.insert(0, "[").unwrap();
+        manager.begin_group();
+        manager.insert(5, "]").unwrap();
+        manager.end_group();
+        manager.delete(1, 2).unwrap();
+        manager.end_group();
+        manager.begin_group();
+        manager.end_group();
+        assert_eq!(manager.text(), "[cd]");
+        assert_eq!(manager.undo_len(), 2);
+
+        assert_eq!(manager.undo().unwrap().to_string(), "undone: delete 2 at 1, insert ']' at 5, insert '[' at 0");
+        assert_eq!(manager.text(), "abcd");
+        assert_eq!(manager.redo().unwrap().to_string(), "redone: insert '[' at 0, insert ']' at 5, delete 2 at 1");
+        assert_eq!(manager.receiver().borrow().to_string(), "[cd]");
+    }
+
+    #[test]
+    fn test_failed_compensation() {
+        let calls = Rc::new(Cell::new(2));
+        let mut manager = UndoManager::new(Grouping::Keystroke);
+        manager.begin_group();
+        manager.execute(Box::new(Flaky { calls: calls.clone() })).unwrap();
+        manager.execute(Box::new(Flaky { calls: calls.clone() })).unwrap();
+        manager.end_group();
+
+        // The last command is undone, the first one fails, and executing the last one again fails too.
+        calls.set(1);
+        assert_eq!(manager.undo(), Err(CommandError::Failed("out of calls".to_string())));
+        assert_eq!(manager.undo_len(), 1);
+
+        calls.set(2);
+        assert_eq!(manager.undo().unwrap().to_string(), "undone: flaky, flaky");
+        calls.set(1);
+        assert!(manager.redo().is_err());
+        assert_eq!(manager.redo_len(), 1);
+        calls.set(2);
+        assert_eq!(manager.redo().unwrap().to_string(), "redone: flaky, flaky");
+    }
+}
@@ -0,0 +1,431 @@
+//! Home automation with several receivers.
+//!
+//! A `Home` holds devices addressed by an identifier: lights, dimmers, thermostats and blinds. A `SetCommand` changes
+//! one setting of one device, such as the setpoint of a thermostat, and a scene changes several devices at once. Scenes
+//! are macro commands: either all their devices are set, or none is.
+//!
+//! The `HomeController` is the invoker. On top of undoing the last command, it can undo the last change of a single
+//! device, whichever command made it: every command records the previous state of the devices it changed.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::rc::Rc;
+use std::result;
+use std::str::FromStr;
+
+use super::macro_command::{MacroCommand, MacroOutcome};
+use super::{Command, CommandError, Light, Result};
+
+/// The state of a device.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Device {
+    /// A light which can be turned on and off and dimmed.
+    Light(Light),
+    /// A dimmer, at some level in percent.
+    Dimmer(u8),
+    /// A thermostat, with a setpoint in degrees Celsius.
+    Thermostat(f64),
+    /// Blinds, open by some percentage.
+    Blinds(u8),
+}
+
+impl Device {
+    /// The kind of device, in lowercase.
+    pub fn kind(&self) -> &'static str {
+        match *self {
+            Device::Light(_)        => "light",
+            Device::Dimmer(_)       => "dimmer",
+            Device::Thermostat(_)   => "thermostat",
+            Device::Blinds(_)       => "blinds",
+        }
+    }
+
+    /// Returns the state of the device once the setting is applied.
+    fn apply(&self, setting: Setting) -> result::Result<Device, String> {
+        match (*self, setting) {
+            (Device::Light(mut light), Setting::Power(true))    => {
+                light.turn_on();
+                Ok(Device::Light(light))
+            },
+            (Device::Light(mut light), Setting::Power(false))   => {
+                light.turn_off();
+                Ok(Device::Light(light))
+            },
+            (Device::Light(mut light), Setting::Brightness(brightness)) => {
+                light.dim(brightness).map_err(|err| err.to_string())?;
+                Ok(Device::Light(light))
+            },
+            (Device::Dimmer(_), Setting::Level(level)) if level <= 100  => Ok(Device::Dimmer(level)),
+            (Device::Thermostat(_), Setting::Setpoint(setpoint)) if (5.0..=30.0).contains(&setpoint) => {
+                Ok(Device::Thermostat(setpoint))
+            },
+            (Device::Blinds(_), Setting::Position(position)) if position <= 100 => Ok(Device::Blinds(position)),
+            (Device::Thermostat(_), Setting::Setpoint(_))   => Err(format!("{} is out of range (5 to 30)", setting)),
+            (device, setting) if device.supports(setting)   => Err(format!("{} is out of range (0 to 100)", setting)),
+            (device, setting)   => Err(format!("a {} does not support '{}'", device.kind(), setting)),
+        }
+    }
+
+    /// Whether the setting applies to this kind of device.
+    fn supports(&self, setting: Setting) -> bool {
+        matches!((*self, setting),
+                 (Device::Light(_), Setting::Power(_)) | (Device::Light(_), Setting::Brightness(_))
+                 | (Device::Dimmer(_), Setting::Level(_)) | (Device::Thermostat(_), Setting::Setpoint(_))
+                 | (Device::Blinds(_), Setting::Position(_)))
+    }
+}
+
+impl fmt::Display for Device {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Device::Light(light)            => write!(f, "light {}", light),
+            Device::Dimmer(level)           => write!(f, "dimmer at {}%", level),
+            Device::Thermostat(setpoint)    => write!(f, "thermostat set to {:.1}°C", setpoint),
+            Device::Blinds(position)        => write!(f, "blinds {}% open", position),
+        }
+    }
+}
+
+/// A setting of a device.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Setting {
+    /// Turns a light on or off.
+    Power(bool),
+    /// Sets the brightness of a light, in percent.
+    Brightness(u8),
+    /// Sets the level of a dimmer, in percent.
+    Level(u8),
+    /// Sets the setpoint of a thermostat, in degrees Celsius.
+    Setpoint(f64),
+    /// Sets how much blinds are open, in percent.
+    Position(u8),
+}
+
+/// Renders the setting as it is parsed, for instance `brightness 40`.
+impl fmt::Display for Setting {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Setting::Power(on)              => write!(f, "{}", if on { "on" } else { "off" }),
+            Setting::Brightness(brightness) => write!(f, "brightness {}", brightness),
+            Setting::Level(level)           => write!(f, "level {}", level),
+            Setting::Setpoint(setpoint)     => write!(f, "setpoint {}", setpoint),
+            Setting::Position(position)     => write!(f, "position {}", position),
+        }
+    }
+}
+
+/// Parses `on`, `off`, `brightness <percent>`, `level <percent>`, `setpoint <degrees>` or `position <percent>`.
+impl FromStr for Setting {
+    type Err = CommandError;
+
+    fn from_str(value: &str) -> Result<Setting> {
+        let invalid = || CommandError::InvalidArguments {
+            command: String::from("SET"),
+            message: format!("invalid setting '{}'", value),
+        };
+        let words: Vec<&str> = value.split_whitespace().collect();
+        let percent = |value: &str| value.trim_end_matches('%').parse::<u8>().map_err(|_| invalid());
+        match *words.as_slice() {
+            ["on"]                  => Ok(Setting::Power(true)),
+            ["off"]                 => Ok(Setting::Power(false)),
+            ["brightness", value]   => Ok(Setting::Brightness(percent(value)?)),
+            ["level", value]        => Ok(Setting::Level(percent(value)?)),
+            ["position", value]     => Ok(Setting::Position(percent(value)?)),
+            ["setpoint", value]     => {
+                value.trim_end_matches("°C").parse().map(Setting::Setpoint).map_err(|_| invalid())
+            },
+            _                       => Err(invalid()),
+        }
+    }
+}
+
+/// What a command did to the devices of a home.
+#[derive(Clone, Debug, PartialEq)]
+pub enum HomeOutcome {
+    /// A device was put in a new state.
+    DeviceSet {
+        /// The identifier of the device.
+        id: String,
+        /// The state of the device.
+        state: Device,
+    },
+    /// A scene was executed or undone.
+    Scene(MacroOutcome),
+}
+
+impl From<MacroOutcome> for HomeOutcome {
+    fn from(outcome: MacroOutcome) -> HomeOutcome {
+        HomeOutcome::Scene(outcome)
+    }
+}
+
+/// Renders a change of a device as `lamp: light on at 40%`.
+impl fmt::Display for HomeOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            HomeOutcome::DeviceSet { ref id, state }    => write!(f, "{}: {}", id, state),
+            HomeOutcome::Scene(ref outcome)             => write!(f, "{}", outcome),
+        }
+    }
+}
+
+/// The receivers: devices addressed by their identifier.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Home {
+    devices: BTreeMap<String, Device>,
+}
+
+/// Renders every device on its own line, as `lamp: light on at 40%`.
+impl fmt::Display for Home {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.devices.iter().try_for_each(|(id, device)| writeln!(f, "{}: {}", id, device))
+    }
+}
+
+/// A home shared between the commands acting on it.
+pub type SharedHome = Rc<RefCell<Home>>;
+
+impl Home {
+    /// Creates a home without devices.
+    pub fn new() -> Home {
+        Home::default()
+    }
+
+    /// Adds a device, replacing any device with the same identifier.
+    pub fn add<S>(&mut self, id: S, device: Device) -> &mut Home where S: Into<String> {
+        self.devices.insert(id.into(), device);
+        self
+    }
+
+    /// Returns the state of a device.
+    pub fn device(&self, id: &str) -> Option<Device> {
+        self.devices.get(id).copied()
+    }
+
+    /// The identifiers of the devices, in alphabetical order.
+    pub fn ids(&self) -> Vec<&str> {
+        self.devices.keys().map(String::as_str).collect()
+    }
+
+    /// Applies a setting to a device, and returns its previous state.
+    fn set(&mut self, id: &str, setting: Setting) -> Result<Device> {
+        let device = self.devices.get_mut(id).ok_or_else(|| CommandError::UnknownDevice(id.to_string()))?;
+        let previous = *device;
+        *device = device.apply(setting).map_err(|reason| CommandError::Failed(format!("{}: {}", id, reason)))?;
+        Ok(previous)
+    }
+
+    /// Puts a device back in a previous state.
+    fn restore(&mut self, id: &str, state: Device) -> Result<()> {
+        let device = self.devices.get_mut(id).ok_or_else(|| CommandError::UnknownDevice(id.to_string()))?;
+        *device = state;
+        Ok(())
+    }
+}
+
+/// A command applying a setting to a device.
+pub struct SetCommand {
+    home: SharedHome,
+    id: String,
+    setting: Setting,
+    previous: Option<Device>,
+}
+
+impl SetCommand {
+    /// Creates a command applying a setting to the device with the given identifier.
+    pub fn new<S>(home: SharedHome, id: S, setting: Setting) -> SetCommand where S: Into<String> {
+        SetCommand {
+            home,
+            id: id.into(),
+            setting,
+            previous: None,
+        }
+    }
+}
+
+impl Command<HomeOutcome> for SetCommand {
+    fn name(&self) -> String {
+        format!("{} {}", self.id, self.setting)
+    }
+
+    fn execute(&mut self) -> Result<HomeOutcome> {
+        let mut home = self.home.borrow_mut();
+        self.previous = Some(home.set(&self.id, self.setting)?);
+        Ok(HomeOutcome::DeviceSet { id: self.id.clone(), state: home.device(&self.id).unwrap() })
+    }
+
+    fn unexecute(&mut self) -> Result<HomeOutcome> {
+        let previous = self.previous.take().ok_or(CommandError::NothingToUndo)?;
+        self.home.borrow_mut().restore(&self.id, previous)?;
+        Ok(HomeOutcome::DeviceSet { id: self.id.clone(), state: previous })
+    }
+}
+
+/// Creates a scene: a macro command applying all settings, or none if one of them fails.
+pub fn scene(home: &SharedHome, name: &str, settings: &[(&str, Setting)]) -> MacroCommand<HomeOutcome> {
+    settings.iter().fold(MacroCommand::new(name), |scene, &(id, setting)| {
+        scene.then(SetCommand::new(Rc::clone(home), id, setting))
+    })
+}
+
+/// The invoker of home automation commands, with undo per command and per device.
+pub struct HomeController {
+    home: SharedHome,
+    history: Vec<Box<dyn Command<HomeOutcome>>>,
+    changes: HashMap<String, Vec<Device>>,
+}
+
+impl HomeController {
+    /// Creates a controller for the given devices.
+    pub fn new(home: Home) -> HomeController {
+        HomeController {
+            home: Rc::new(RefCell::new(home)),
+            history: Vec::new(),
+            changes: HashMap::new(),
+        }
+    }
+
+    /// The devices controlled, to create commands acting on them.
+    pub fn receiver(&self) -> SharedHome {
+        Rc::clone(&self.home)
+    }
+
+    /// Returns the state of a device.
+    pub fn state(&self, id: &str) -> Option<Device> {
+        self.home.borrow().device(id)
+    }
+
+    /// Executes a command given as `<device> <setting>`, for instance `thermostat setpoint 21.5`.
+    pub fn execute_command(&mut self, line: &str) -> Result<HomeOutcome> {
+        let (id, setting) = match line.trim().split_once(' ') {
+            Some((id, setting)) => (id, setting.parse()?),
+            None                => return Err(CommandError::InvalidArguments {
+                command: String::from("SET"),
+                message: String::from("expected '<device> <setting>'"),
+            }),
+        };
+        self.execute(Box::new(SetCommand::new(self.receiver(), id, setting)))
+    }
+
+    /// Executes a command, and records the previous state of every device it changed.
+    pub fn execute(&mut self, mut command: Box<dyn Command<HomeOutcome>>) -> Result<HomeOutcome> {
+        let before = self.home.borrow().clone();
+        let outcome = command.execute()?;
+        for (id, state) in self.changed_since(&before) {
+            self.changes.entry(id).or_default().push(state);
+        }
+        self.history.push(command);
+        Ok(outcome)
+    }
+
+    /// Undoes the last command.
+    pub fn undo(&mut self) -> Result<HomeOutcome> {
+        let mut command = self.history.pop().ok_or(CommandError::NothingToUndo)?;
+        let before = self.home.borrow().clone();
+        match command.unexecute() {
+            Ok(outcome) => {
+                for (id, _) in self.changed_since(&before) {
+                    self.changes.get_mut(&id).and_then(|changes| changes.pop());
+                }
+                Ok(outcome)
+            },
+            Err(err)    => {
+                self.history.push(command);
+                Err(err)
+            },
+        }
+    }
+
+    /// Puts a single device back in the state it had before its last change. Other devices are not affected, even if
+    /// they were changed by the same command.
+    pub fn undo_device(&mut self, id: &str) -> Result<HomeOutcome> {
+        if self.state(id).is_none() {
+            return Err(CommandError::UnknownDevice(id.to_string()));
+        }
+        let previous = self.changes.get_mut(id).and_then(|changes| changes.pop()).ok_or(CommandError::NothingToUndo)?;
+        self.home.borrow_mut().restore(id, previous)?;
+        Ok(HomeOutcome::DeviceSet { id: id.to_string(), state: previous })
+    }
+
+    /// The devices whose state differs from a snapshot, with their state in the snapshot.
+    fn changed_since(&self, before: &Home) -> Vec<(String, Device)> {
+        let home = self.home.borrow();
+        before.devices.iter()
+            .filter(|&(id, state)| home.device(id) != Some(*state))
+            .map(|(id, state)| (id.clone(), *state))
+            .collect()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn living_room() -> HomeController {
+        let mut home = Home::new();
+        home.add("lamp", Device::Light(Light::new()))
+            .add("dimmer", Device::Dimmer(100))
+            .add("thermostat", Device::Thermostat(19.0))
+            .add("blinds", Device::Blinds(100));
+        HomeController::new(home)
+    }
+
+    #[test]
+    fn test_home_devices() {
+        let mut controller = living_room();
+        assert_eq!(controller.receiver().borrow().ids(), vec!["blinds", "dimmer", "lamp", "thermostat"]);
+
+        assert_eq!(controller.execute_command("lamp on").unwrap().to_string(), "lamp: light on at 100%");
+        controller.execute_command("lamp brightness 40").unwrap();
+        assert_eq!(controller.execute_command("thermostat setpoint 21.5").unwrap().to_string(),
+                   "thermostat: thermostat set to 21.5°C");
+        assert_eq!(controller.state("lamp"), Some(Device::Light(Light::from_state(true, 40))));
+
+        let error = |controller: &mut HomeController, line: &str| controller.execute_command(line).unwrap_err();
+        assert_eq!(error(&mut controller, "garage on"), CommandError::UnknownDevice("garage".to_string()));
+        assert_eq!(error(&mut controller, "blinds brightness 20").to_string(),
+                   "blinds: a blinds does not support 'brightness 20'");
+        assert_eq!(error(&mut controller, "thermostat setpoint 45").to_string(),
+                   "thermostat: setpoint 45 is out of range (5 to 30)");
+        assert_eq!(error(&mut controller, "dimmer level 140").to_string(),
+                   "dimmer: level 140 is out of range (0 to 100)");
+        assert_eq!(error(&mut controller, "lamp").to_string(),
+                   "invalid arguments for SET: expected '<device> <setting>'");
+
+        // Undo the changes of the lamp only, the thermostat keeps its setpoint.
+        controller.undo_device("lamp").unwrap();
+        assert_eq!(controller.state("lamp").unwrap().to_string(), "light on at 100%");
+        controller.undo_device("lamp").unwrap();
+        assert_eq!(controller.state("lamp").unwrap().to_string(), "light off");
+        assert_eq!(controller.undo_device("lamp"), Err(CommandError::NothingToUndo));
+        assert_eq!(controller.state("thermostat"), Some(Device::Thermostat(21.5)));
+    }
+
+    #[test]
+    fn test_home_scene() {
+        let mut controller = living_room();
+        let home = controller.receiver();
+        let movie = scene(&home, "movie", &[("lamp", Setting::Power(true)), ("lamp", Setting::Brightness(20)),
+                                            ("blinds", Setting::Position(0)), ("dimmer", Setting::Level(10))]);
+        assert_eq!(controller.execute(Box::new(movie)).unwrap().to_string(),
+                   "macro 'movie' executed: lamp on, lamp brightness 20, blinds position 0, dimmer level 10");
+        assert_eq!(controller.state("blinds"), Some(Device::Blinds(0)));
+
+        // Scenes are atomic.
+        let broken = scene(&home, "broken", &[("blinds", Setting::Position(100)), ("oven", Setting::Power(true))]);
+        assert!(controller.execute(Box::new(broken)).is_err());
+        assert_eq!(controller.state("blinds"), Some(Device::Blinds(0)));
+
+        // Opening the blinds again keeps the rest of the scene.
+        controller.undo_device("blinds").unwrap();
+        assert_eq!(controller.state("blinds"), Some(Device::Blinds(100)));
+        assert_eq!(controller.state("dimmer"), Some(Device::Dimmer(10)));
+
+        controller.undo().unwrap();
+        assert_eq!(controller.state("lamp").unwrap().to_string(), "light off");
+        assert_eq!(controller.state("dimmer"), Some(Device::Dimmer(100)));
+        assert_eq!(controller.undo_device("dimmer"), Err(CommandError::NothingToUndo));
+    }
+}
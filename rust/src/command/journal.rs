@@ -0,0 +1,378 @@
+//! Persistent command journal.
+//!
+//! A `JournaledSwitch` appends every command it executes, undoes or redoes to a journal file before applying it, and
+//! takes the entry back if the command fails. After a crash, opening the journal again replays it into a fresh
+//! `Switch`, which recovers the state of the light as well as the commands that can be undone. The journal is a
+//! plain-text file with one entry per line:
+//!
+//! ```text
+//! SNAPSHOT off 100
+//! EXEC ON
+//! EXEC DIM 40
+//! UNDO
+//! REDO
+//! ```
+//!
+//! As the journal only grows, it can be compacted: the entries are replaced by a snapshot of the state of the light,
+//! followed by the last few commands of the history such that they can still be undone.
+//!
+//! Lines that cannot be parsed, or which execute commands unknown to the registry, are reported as
+//! `JournalError::Corrupt`. A last line without a line feed was cut short by a crash while it was being written, it is
+//! reported as `JournalError::Truncated`.
+
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::result;
+use std::str::FromStr;
+
+use super::{CommandError, Light, Outcome, Registry, SharedLight, Switch};
+
+/// Result of journal operations.
+pub type Result<T> = result::Result<T, JournalError>;
+
+/// Errors raised when reading, writing or replaying a journal.
+#[derive(Debug, PartialEq)]
+pub enum JournalError {
+    /// The journal could not be read or written.
+    Io {
+        /// The path of the journal.
+        path: String,
+        /// The reason of the failure.
+        message: String,
+    },
+    /// A line of the journal is not a valid entry.
+    Corrupt {
+        /// The line number (starting at 1).
+        line: usize,
+        /// The content of the line.
+        content: String,
+    },
+    /// The last line of the journal is incomplete.
+    Truncated {
+        /// The line number (starting at 1).
+        line: usize,
+    },
+    /// An entry could not be applied when replaying the journal.
+    Replay {
+        /// The line number (starting at 1).
+        line: usize,
+        /// The error raised by the entry.
+        error: CommandError,
+    },
+    /// A command failed. It was not written to the journal.
+    Command(CommandError),
+}
+
+impl fmt::Display for JournalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            JournalError::Io { ref path, ref message }  => write!(f, "cannot access {}: {}", path, message),
+            JournalError::Corrupt { line, ref content } => write!(f, "line {}: corrupt entry '{}'", line, content),
+            JournalError::Truncated { line }            => write!(f, "line {}: truncated entry", line),
+            JournalError::Replay { line, ref error }    => write!(f, "line {}: cannot replay entry: {}", line, error),
+            JournalError::Command(ref error)            => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Error for JournalError {}
+
+impl From<CommandError> for JournalError {
+    fn from(error: CommandError) -> JournalError {
+        JournalError::Command(error)
+    }
+}
+
+/// An entry of the journal.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Entry {
+    /// A command was executed, given by its name.
+    Execute(String),
+    /// The last command was undone.
+    Undo,
+    /// The last undone command was redone.
+    Redo,
+    /// The light was in the given state, with an empty history.
+    Snapshot(Light),
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Entry::Execute(ref name)    => write!(f, "EXEC {}", name),
+            Entry::Undo                 => write!(f, "UNDO"),
+            Entry::Redo                 => write!(f, "REDO"),
+            Entry::Snapshot(light)      => {
+                write!(f, "SNAPSHOT {} {}", if light.is_on() { "on" } else { "off" }, light.brightness())
+            },
+        }
+    }
+}
+
+/// Parses the syntax of an entry. Whether an executed command exists is only checked by `parse`.
+impl FromStr for Entry {
+    type Err = ();
+
+    fn from_str(line: &str) -> result::Result<Entry, ()> {
+        let words: Vec<&str> = line.split(' ').collect();
+        match words.as_slice() {
+            ["EXEC", name @ ..] if !name.join("").trim().is_empty() => Ok(Entry::Execute(name.join(" "))),
+            ["UNDO"]                                => Ok(Entry::Undo),
+            ["REDO"]                                => Ok(Entry::Redo),
+            ["SNAPSHOT", state, brightness]         => {
+                let on = match *state {
+                    "on"    => true,
+                    "off"   => false,
+                    _       => return Err(()),
+                };
+                match brightness.parse() {
+                    Ok(brightness) if brightness <= 100 => Ok(Entry::Snapshot(Light::from_state(on, brightness))),
+                    _                                   => Err(()),
+                }
+            },
+            _                                       => Err(()),
+        }
+    }
+}
+
+/// Reads the entries of a journal, whose commands are created by the given registry. A missing journal has no entries.
+pub fn read<P>(path: P, registry: &Registry) -> Result<Vec<Entry>> where P: AsRef<Path> {
+    let path = path.as_ref();
+    let content = match fs::read_to_string(path) {
+        Ok(content)                                                 => content,
+        Err(ref err) if err.kind() == std::io::ErrorKind::NotFound  => String::new(),
+        Err(err)                                                    => return Err(io_error(path, err)),
+    };
+    parse(&content, registry)
+}
+
+/// Parses the content of a journal, whose commands are created by the given registry.
+pub fn parse(content: &str, registry: &Registry) -> Result<Vec<Entry>> {
+    let lines: Vec<&str> = content.lines().collect();
+    if !content.is_empty() && !content.ends_with('\n') {
+        return Err(JournalError::Truncated { line: lines.len() });
+    }
+    let light = SharedLight::default();
+    lines.iter().enumerate().map(|(idx, line)| {
+        match line.parse() {
+            Ok(Entry::Execute(ref name)) if registry.create(name, &light).is_err()  => Err(()),
+            entry                                                                   => entry,
+        }.map_err(|_| JournalError::Corrupt { line: idx + 1, content: line.to_string() })
+    }).collect()
+}
+
+/// Replays entries into a fresh switch.
+pub fn replay(entries: &[Entry]) -> Result<Switch> {
+    let mut switch = Switch::new();
+    for (idx, entry) in entries.iter().enumerate() {
+        apply(&mut switch, entry).map_err(|error| JournalError::Replay { line: idx + 1, error })?;
+    }
+    Ok(switch)
+}
+
+/// Applies an entry to a switch.
+fn apply(switch: &mut Switch, entry: &Entry) -> result::Result<Outcome, CommandError> {
+    match *entry {
+        Entry::Execute(ref name)    => switch.execute_command(name),
+        Entry::Undo                 => switch.undo(),
+        Entry::Redo                 => switch.redo(),
+        Entry::Snapshot(light)      => {
+            switch.reset(light);
+            Ok(Outcome::Restored(light))
+        },
+    }
+}
+
+fn io_error(path: &Path, err: std::io::Error) -> JournalError {
+    JournalError::Io {
+        path: path.display().to_string(),
+        message: err.to_string(),
+    }
+}
+
+/// A switch writing its history to a journal.
+pub struct JournaledSwitch {
+    switch: Switch,
+    path: PathBuf,
+    file: File,
+}
+
+impl JournaledSwitch {
+    /// Opens a journal, creating it if needed, and replays it to recover the state of the switch.
+    pub fn open<P>(path: P) -> Result<JournaledSwitch> where P: AsRef<Path> {
+        let path = path.as_ref().to_path_buf();
+        let switch = replay(&read(&path, &Registry::standard())?)?;
+        let file = OpenOptions::new().create(true).append(true).open(&path).map_err(|err| io_error(&path, err))?;
+        Ok(JournaledSwitch { switch, path, file })
+    }
+
+    /// The switch, as recovered from the journal and updated since.
+    pub fn switch(&self) -> &Switch {
+        &self.switch
+    }
+
+    /// Executes a command given by its name and arguments, as registered in the standard `Registry`, and writes it to
+    /// the journal. The journal holds the name of the command as created by the registry rather than the given line,
+    /// such that extra whitespace cannot make the journal unreadable.
+    pub fn execute_command(&mut self, name: &str) -> Result<Outcome> {
+        let command = self.switch.registry().create(name, &self.switch.receiver())?;
+        self.apply(Entry::Execute(command.name()))
+    }
+
+    /// Undoes the last command and writes it to the journal.
+    pub fn undo(&mut self) -> Result<Outcome> {
+        self.apply(Entry::Undo)
+    }
+
+    /// Redoes the last undone command and writes it to the journal.
+    pub fn redo(&mut self) -> Result<Outcome> {
+        self.apply(Entry::Redo)
+    }
+
+    /// Rewrites the journal as a snapshot followed by the last `keep` commands of the history, which can still be
+    /// undone after the journal is replayed. Commands that were undone can no longer be redone.
+    pub fn compact(&mut self, keep: usize) -> Result<()> {
+        let history = self.switch.history();
+        let tail = &history[history.len().saturating_sub(keep)..];
+        let mut scratch = replay(&read(&self.path, self.switch.registry())?)?;
+        for _ in tail {
+            scratch.undo()?;
+        }
+        let mut entries = vec![Entry::Snapshot(scratch.light())];
+        entries.extend(tail.iter().map(|name| Entry::Execute(name.clone())));
+        let compacted = replay(&entries)?;
+
+        // Write the compacted journal aside and move it over the old one, which stays intact if writing fails. The
+        // compacted journal is opened before it is moved, such that entries are never appended to the old one.
+        let temporary = self.path.with_extension("compact");
+        let content: String = entries.iter().map(|entry| format!("{}\n", entry)).collect();
+        fs::write(&temporary, content).map_err(|err| io_error(&temporary, err))?;
+        let file = OpenOptions::new().append(true).open(&temporary).map_err(|err| io_error(&temporary, err))?;
+        fs::rename(&temporary, &self.path).map_err(|err| io_error(&self.path, err))?;
+        self.file = file;
+        self.switch = compacted;
+        Ok(())
+    }
+
+    /// Writes an entry to the journal and applies it to the switch. The entry is removed from the journal if it could
+    /// not be written completely or if applying it failed, such that the journal never holds more than the switch.
+    fn apply(&mut self, entry: Entry) -> Result<Outcome> {
+        let len = self.file.metadata().map_err(|err| io_error(&self.path, err))?.len();
+        if let Err(err) = writeln!(self.file, "{}", entry).and_then(|_| self.file.flush()) {
+            let _ = self.file.set_len(len);
+            return Err(io_error(&self.path, err));
+        }
+        match apply(&mut self.switch, &entry) {
+            Ok(outcome) => Ok(outcome),
+            Err(error)  => {
+                self.file.set_len(len).map_err(|err| io_error(&self.path, err))?;
+                Err(JournalError::Command(error))
+            },
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn journal(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("patterns-journal-{}-{}.log", name, std::process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_journal_replay() {
+        let path = journal("replay");
+        {
+            let mut switch = JournaledSwitch::open(&path).unwrap();
+            switch.execute_command("ON").unwrap();
+            switch.execute_command("DIM 40").unwrap();
+            switch.execute_command("DIM 70").unwrap();
+            switch.undo().unwrap();
+            assert_eq!(switch.execute_command("DIM 101").unwrap_err().to_string(),
+                       "invalid arguments for DIM: invalid brightness '101', expected 0 to 100");
+            assert_eq!(switch.execute_command("BLINK"),
+                       Err(JournalError::Command(CommandError::UnknownCommand("BLINK".to_string()))));
+        }
+        assert_eq!(fs::read_to_string(&path).unwrap(), "EXEC ON\nEXEC DIM 40\nEXEC DIM 70\nUNDO\n");
+
+        let mut recovered = JournaledSwitch::open(&path).unwrap();
+        assert_eq!(recovered.switch().light().to_string(), "on at 40%");
+        assert_eq!(recovered.redo(), Ok(Outcome::Dimmed(70)));
+
+        for _ in 0..5 {
+            recovered.execute_command("OFF").unwrap();
+            recovered.execute_command("ON").unwrap();
+        }
+        recovered.compact(2).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "SNAPSHOT on 70\nEXEC OFF\nEXEC ON\n");
+        recovered.undo().unwrap();
+        drop(recovered);
+
+        let recovered = JournaledSwitch::open(&path).unwrap();
+        assert_eq!(recovered.switch().light().to_string(), "off");
+        assert_eq!(recovered.switch().history(), vec!["OFF"]);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_journal_errors() {
+        let registry = Registry::standard();
+        let parse = |content: &str| parse(content, &registry);
+        assert_eq!(parse("EXEC ON\nEXEC DIM"), Err(JournalError::Truncated { line: 2 }));
+        assert_eq!(parse("EXEC ON\nEXEC DIM\n"), Err(JournalError::Corrupt { line: 2, content: "EXEC DIM".into() }));
+        assert_eq!(parse("EXEC \n"), Err(JournalError::Corrupt { line: 1, content: "EXEC ".into() }));
+        assert_eq!(parse("SNAPSHOT on 140\n"),
+                   Err(JournalError::Corrupt { line: 1, content: "SNAPSHOT on 140".into() }));
+        assert_eq!(parse("\n").unwrap_err().to_string(), "line 1: corrupt entry ''");
+
+        let entries = parse("EXEC OFF\nUNDO\nUNDO\n").unwrap();
+        assert_eq!(replay(&entries).err(), Some(JournalError::Replay { line: 3, error: CommandError::NothingToUndo }));
+        let entries = parse("SNAPSHOT off 30\nEXEC DIM 50\n").unwrap();
+        assert!(matches!(replay(&entries), Err(JournalError::Replay { line: 2, .. })));
+
+        let path = journal("truncated");
+        fs::write(&path, "EXEC ON\nEXEC O").unwrap();
+        assert_eq!(JournaledSwitch::open(&path).err(), Some(JournalError::Truncated { line: 2 }));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_journal_canonical_names() {
+        let path = journal("canonical");
+        {
+            let mut switch = JournaledSwitch::open(&path).unwrap();
+            switch.execute_command("ON\n").unwrap();
+            switch.execute_command("  DIM\t40 ").unwrap();
+            assert!(switch.execute_command("\n").is_err());
+        }
+        assert_eq!(fs::read_to_string(&path).unwrap(), "EXEC ON\nEXEC DIM 40\n");
+        let recovered = JournaledSwitch::open(&path).unwrap();
+        assert_eq!(recovered.switch().light().to_string(), "on at 40%");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_journal_write_failure() {
+        let path = journal("readonly");
+        fs::write(&path, "EXEC ON\n").unwrap();
+        // A journal opened for reading only fails every write.
+        let mut switch = JournaledSwitch {
+            switch: replay(&read(&path, &Registry::standard()).unwrap()).unwrap(),
+            file: File::open(&path).unwrap(),
+            path: path.clone(),
+        };
+        assert!(matches!(switch.execute_command("OFF"), Err(JournalError::Io { .. })));
+        assert!(matches!(switch.undo(), Err(JournalError::Io { .. })));
+        assert_eq!(switch.switch().light().to_string(), "on at 100%");
+        assert_eq!(switch.switch().history(), vec!["ON"]);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "EXEC ON\n");
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,320 @@
+//! Transactional macro commands.
+//!
+//! A `MacroCommand` executes a sequence of commands as a single one. Its steps run in order, and if one of them fails,
+//! the steps executed before it are undone in reverse order such that the receivers are left as they were. A
+//! `MacroReport` tells which step failed and which steps were rolled back.
+//!
+//! Macro commands are commands themselves, hence they can be nested: a failing inner macro rolls itself back before
+//! the outer macro rolls back its own previous steps. Undoing a macro is atomic too: if a step cannot be undone, the
+//! steps undone before it are executed again in order.
+
+use std::fmt;
+
+use super::{Command, CommandError, Outcome, Result};
+
+/// A step of a macro command that failed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StepFailure {
+    /// The position of the step in the macro, starting at 1.
+    pub step: usize,
+    /// The name of the command.
+    pub command: String,
+    /// The error raised by the command.
+    pub error: CommandError,
+}
+
+/// The account of the execution of a macro command.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MacroReport {
+    /// The name of the macro.
+    pub name: String,
+    /// The names of the steps that were executed successfully, or undone successfully if the macro was being undone.
+    pub executed: Vec<String>,
+    /// The step that failed, if any.
+    pub failure: Option<StepFailure>,
+    /// The names of the steps that were undone after the failure, in the order in which they were undone. If the macro
+    /// was being undone, these are the steps executed again.
+    pub rolled_back: Vec<String>,
+    /// The steps that could not be undone.
+    pub rollback_failures: Vec<StepFailure>,
+}
+
+impl MacroReport {
+    fn new(name: &str) -> MacroReport {
+        MacroReport {
+            name: name.to_string(),
+            executed: Vec::new(),
+            failure: None,
+            rolled_back: Vec::new(),
+            rollback_failures: Vec::new(),
+        }
+    }
+
+    /// Whether all steps were executed.
+    pub fn is_success(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+impl fmt::Display for MacroReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let failure = match self.failure {
+            Some(ref failure)   => failure,
+            None                => return write!(f, "macro '{}' executed: {}", self.name, list(&self.executed)),
+        };
+        write!(f, "macro '{}' failed at step {} ({}): {}; rolled back: {}", self.name, failure.step, failure.command,
+               failure.error, list(&self.rolled_back))?;
+        for failure in &self.rollback_failures {
+            write!(f, "; could not roll back step {} ({}): {}", failure.step, failure.command, failure.error)?;
+        }
+        Ok(())
+    }
+}
+
+/// What a macro command did.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MacroOutcome {
+    /// All steps of the macro were executed.
+    Executed {
+        /// The name of the macro.
+        name: String,
+        /// The names of its steps.
+        steps: Vec<String>,
+    },
+    /// All steps of the macro were undone.
+    Undone {
+        /// The name of the macro.
+        name: String,
+        /// The names of its steps, in the order in which they were undone.
+        steps: Vec<String>,
+    },
+}
+
+impl fmt::Display for MacroOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MacroOutcome::Executed { ref name, ref steps }  => write!(f, "macro '{}' executed: {}", name, list(steps)),
+            MacroOutcome::Undone { ref name, ref steps }    => write!(f, "macro '{}' undone: {}", name, list(steps)),
+        }
+    }
+}
+
+/// Joins the names of steps, or tells there is none.
+pub(super) fn list(names: &[String]) -> String {
+    if names.is_empty() { String::from("nothing") } else { names.join(", ") }
+}
+
+/// A command executing a sequence of commands atomically. Its steps all report outcomes of type `O`.
+pub struct MacroCommand<O = Outcome> {
+    name: String,
+    commands: Vec<Box<dyn Command<O>>>,
+}
+
+impl<O> MacroCommand<O> {
+    /// Creates an empty macro.
+    pub fn new<S>(name: S) -> MacroCommand<O> where S: Into<String> {
+        MacroCommand {
+            name: name.into(),
+            commands: Vec::new(),
+        }
+    }
+
+    /// Appends a step to the macro.
+    pub fn then<C>(mut self, command: C) -> Self where C: Command<O> + 'static {
+        self.commands.push(Box::new(command));
+        self
+    }
+
+    /// Appends an already boxed step to the macro.
+    pub fn push(&mut self, command: Box<dyn Command<O>>) {
+        self.commands.push(command);
+    }
+
+    /// The number of steps of the macro.
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    /// Whether the macro has no steps.
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// Executes the steps in order. If one fails, the steps executed before it are undone in reverse order.
+    pub fn run(&mut self) -> MacroReport {
+        let mut report = MacroReport::new(&self.name);
+        for (idx, command) in self.commands.iter_mut().enumerate() {
+            match command.execute() {
+                Ok(_)       => report.executed.push(command.name()),
+                Err(error)  => {
+                    report.failure = Some(StepFailure { step: idx + 1, command: command.name(), error });
+                    break;
+                },
+            }
+        }
+        if report.failure.is_some() {
+            let executed = report.executed.len();
+            for (idx, command) in self.commands[..executed].iter_mut().enumerate().rev() {
+                match command.unexecute() {
+                    Ok(_)       => report.rolled_back.push(command.name()),
+                    Err(error)  => {
+                        report.rollback_failures.push(StepFailure { step: idx + 1, command: command.name(), error });
+                    },
+                }
+            }
+        }
+        report
+    }
+}
+
+/// A macro reports its own outcome, which every receiver supporting macros wraps in its outcome type.
+impl<O> Command<O> for MacroCommand<O> where O: From<MacroOutcome> {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    /// Runs the macro. A failure is reported as `CommandError::RolledBack`.
+    fn execute(&mut self) -> Result<O> {
+        let report = self.run();
+        if report.is_success() {
+            Ok(MacroOutcome::Executed { name: report.name, steps: report.executed }.into())
+        } else {
+            Err(CommandError::RolledBack(Box::new(report)))
+        }
+    }
+
+    /// Undoes all steps in reverse order. If one fails, the steps undone before it are executed again in order, and
+    /// the failure is reported as `CommandError::RolledBack`.
+    fn unexecute(&mut self) -> Result<O> {
+        let mut report = MacroReport::new(&self.name);
+        for (idx, command) in self.commands.iter_mut().enumerate().rev() {
+            match command.unexecute() {
+                Ok(_)       => report.executed.push(command.name()),
+                Err(error)  => {
+                    report.failure = Some(StepFailure { step: idx + 1, command: command.name(), error });
+                    break;
+                },
+            }
+        }
+        let failed = match report.failure {
+            Some(ref failure)   => failure.step,
+            None                => return Ok(MacroOutcome::Undone { name: report.name, steps: report.executed }.into()),
+        };
+        for (idx, command) in self.commands.iter_mut().enumerate().skip(failed) {
+            match command.execute() {
+                Ok(_)       => report.rolled_back.push(command.name()),
+                Err(error)  => {
+                    report.rollback_failures.push(StepFailure { step: idx + 1, command: command.name(), error });
+                },
+            }
+        }
+        Err(CommandError::RolledBack(Box::new(report)))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{DimCommand, LightOffCommand, LightOnCommand, Switch};
+
+    /// A command which does nothing, and cannot be undone.
+    struct Irreversible;
+
+    impl Command for Irreversible {
+        fn name(&self) -> String {
+            "IRREVERSIBLE".to_string()
+        }
+
+        fn execute(&mut self) -> Result<Outcome> {
+            Ok(Outcome::TurnedOn)
+        }
+
+        fn unexecute(&mut self) -> Result<Outcome> {
+            Err(CommandError::Failed("cannot be undone".to_string()))
+        }
+    }
+
+    #[test]
+    fn test_macro_command() {
+        let mut switch = Switch::new();
+        let evening = MacroCommand::new("evening")
+            .then(LightOnCommand::new(switch.receiver()))
+            .then(DimCommand::new(switch.receiver(), 30));
+        assert_eq!(switch.execute(Box::new(evening)).unwrap().to_string(), "macro 'evening' executed: ON, DIM 30");
+        assert_eq!(switch.light().to_string(), "on at 30%");
+
+        assert_eq!(switch.undo(), Ok(Outcome::Macro(MacroOutcome::Undone {
+            name: "evening".to_string(),
+            steps: vec!["DIM 30".to_string(), "ON".to_string()],
+        })));
+        assert_eq!(switch.light().to_string(), "off");
+
+        let broken = MacroCommand::new("broken")
+            .then(LightOnCommand::new(switch.receiver()))
+            .then(DimCommand::new(switch.receiver(), 20))
+            .then(DimCommand::new(switch.receiver(), 150));
+        let report = match switch.execute(Box::new(broken)) {
+            Err(CommandError::RolledBack(report))   => report,
+            other                                   => panic!("unexpected result: {:?}", other),
+        };
+        assert_eq!(report.executed, vec!["ON", "DIM 20"]);
+        assert_eq!(report.failure.as_ref().map(|failure| failure.step), Some(3));
+        assert_eq!(report.rolled_back, vec!["DIM 20", "ON"]);
+        assert_eq!(report.to_string(), "macro 'broken' failed at step 3 (DIM 150): invalid brightness 150%, at most \
+                                        100% is allowed; rolled back: DIM 20, ON");
+        assert_eq!(switch.light().to_string(), "off");
+        assert!(switch.can_redo());
+    }
+
+    #[test]
+    fn test_nested_macro_command() {
+        let switch = Switch::new();
+        let light = switch.receiver();
+        let mut outer = MacroCommand::new("outer")
+            .then(LightOnCommand::new(light.clone()))
+            .then(MacroCommand::new("inner")
+                .then(DimCommand::new(light.clone(), 50))
+                .then(LightOffCommand::new(light.clone()))
+                .then(DimCommand::new(light.clone(), 10)));
+
+        let report = outer.run();
+        let failure = report.failure.unwrap();
+        assert_eq!((failure.step, failure.command.as_str()), (2, "inner"));
+        assert_eq!(report.rolled_back, vec!["ON"]);
+        match failure.error {
+            CommandError::RolledBack(inner) => {
+                assert_eq!(inner.executed, vec!["DIM 50", "OFF"]);
+                assert_eq!(inner.rolled_back, vec!["OFF", "DIM 50"]);
+                assert_eq!(inner.failure.unwrap().error,
+                           CommandError::Failed("cannot dim a light which is off".to_string()));
+            },
+            other                           => panic!("unexpected error: {:?}", other),
+        }
+        assert_eq!(switch.light(), super::super::Light::new());
+        assert!(MacroCommand::<Outcome>::new("empty").run().is_success());
+    }
+
+    #[test]
+    fn test_macro_command_undo_failure() {
+        let mut switch = Switch::new();
+        let partial = MacroCommand::new("partial")
+            .then(LightOnCommand::new(switch.receiver()))
+            .then(Irreversible)
+            .then(DimCommand::new(switch.receiver(), 30));
+        switch.execute(Box::new(partial)).unwrap();
+
+        // The step undone before the failure is executed again, and the macro stays in the history.
+        let report = match switch.undo() {
+            Err(CommandError::RolledBack(report))   => report,
+            other                                   => panic!("unexpected result: {:?}", other),
+        };
+        assert_eq!(report.executed, vec!["DIM 30"]);
+        assert_eq!(report.failure.as_ref().map(|failure| failure.step), Some(2));
+        assert_eq!(report.rolled_back, vec!["DIM 30"]);
+        assert!(report.rollback_failures.is_empty());
+        assert_eq!(switch.light().to_string(), "on at 30%");
+        assert!(switch.can_undo());
+        assert!(!switch.can_redo());
+    }
+}
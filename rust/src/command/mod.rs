@@ -0,0 +1,627 @@
+//! Command design pattern.
+//!
+//! # Theory
+//! This is a behavioural design pattern. It is used to encapsulate a request as an object, thereby letting you
+//! parameterise clients with different requests, queue or log requests, and support undoable operations.
+//!
+//! In other words, it lets you turn the request itself into an object. It should be applied when it is necessary
+//! to issue requests to objects without knowing anything about the operation being requested or the receiver of the
+//! request. It decouples the object that invokes the operation from the one having the knowledge to perform it,
+//! allowing more flexibility in the user interface.
+//!
+//! This supports _undo-redo_ operations. The command's `execute()` operation can store the initial state (or any
+//! information required to invert the execution) for reversing its effect. To allow _undo_, a history list must store
+//! all issued commands and an `unexecute()` operation must be supported by the `Command` interface. The prototype
+//! pattern can be used to copy and store commands in the history list.
+//!
+//! This implements _transactional behaviour_ - a list of operations to be performed, if one operation fails, a rollback
+//! is executed.
+//!
+//! # Participants
+//! - `Command`: declares an interface for executing command (`execute()`) and reversing it (`unexecute()`).
+//! - `LightOnCommand`, `LightOffCommand`, `ToggleCommand`, `DimCommand`: concrete commands specifying a receiver-action
+//!   pair by storing the receiver as a variable and invoking one or more operations on it when requested using
+//!   `execute()`.
+//! - `Switch`: the invoker. This contains a queue of commands that have been requested. This allows the _undo-redo_
+//!   actions (`undo()` and `redo()`). It can decide when commands are executed and if they should be delayed.
+//! - `Light`: the receiver. It knows how to perform the operations associated with carrying out a request.
+//!
+//! # Modifications and Strategies
+//! - _Macro commands_ consisting of a sequence of elementary commands. The `macro_command` module implements them as
+//!   transactions: if a command fails, the ones executed before it are undone in reverse order.
+//! - Commands can be written to a _journal_ when they are executed, and replayed to rebuild the state of the receiver
+//!   after a crash. The `journal` module stores the history of a `Switch` in a text file.
+//! - Callbacks store the command objects somewhere to be called at a later point in time. Requests can be specified,
+//!   queued, and executed at different times. The lifetime of the command object is then independent of the original
+//!   request.
+//!   The `scheduler` module queues commands with a due time and an optional repeat interval.
+//! - An invoker can control several receivers. The `home` module addresses lights, dimmers, thermostats and blinds by
+//!   identifier, sets many of them at once with scenes, and undoes the changes of a single device.
+//! - Every receiver reports what commands did to it in its own terms: `Command` is generic over the outcome returned by
+//!   `execute()`, and the light, the home devices and the text buffer each have their own outcome type.
+//! - Commands can be created from their name and arguments, such as `DIM 40`, by a `Registry` of command factories.
+//!   This lets users issue commands as text, and new commands can be registered without changing the invoker.
+//! - _Wizards_: changes to be applied are stored and only executed once some `finish()` is called. The `wizard` module
+//!   previews the pending changes, and applies them atomically if validation accepts the result.
+//! - One can modify undo-redo mechanisms to group operations together. For instance in a text editor, not every key
+//!   press when writing something is undoable, instead they are undoable in reasonable batches. The `editor` module
+//!   groups edits by word, by time window or explicitly.
+//!
+//! # Known Uses
+//! - Wizard
+//! - Undo-redo
+//! - Log-files and re-execution
+//! - Transactional Operations
+//!
+//! # Example
+//! ```
+//! use std::cell::RefCell;
+//! use std::rc::Rc;
+//! use patterns::command::{Command, Light, LightOnCommand};
+//!
+//! let light = Rc::new(RefCell::new(Light::new()));
+//! let mut command = LightOnCommand::new(Rc::clone(&light));
+//! assert_eq!(command.execute()?.to_string(), "light turned on");
+//! assert!(light.borrow().is_on());
+//! command.unexecute()?;
+//! assert!(!light.borrow().is_on());
+//! # Ok::<(), patterns::command::CommandError>(())
+//! ```
+
+pub mod editor;
+pub mod home;
+pub mod journal;
+pub mod macro_command;
+pub mod registry;
+pub mod scheduler;
+pub mod wizard;
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::rc::Rc;
+use std::result;
+
+use self::macro_command::{MacroOutcome, MacroReport};
+pub use self::registry::Registry;
+
+/// Result of executing a command.
+pub type Result<T> = result::Result<T, CommandError>;
+
+/// Errors raised when executing or undoing commands.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CommandError {
+    /// No command is registered under the name.
+    UnknownCommand(String),
+    /// No device has the identifier.
+    UnknownDevice(String),
+    /// The arguments given to a command are invalid.
+    InvalidArguments {
+        /// The name of the command.
+        command: String,
+        /// What is wrong with the arguments.
+        message: String,
+    },
+    /// The receiver could not carry out the command. Nothing was changed.
+    Failed(String),
+    /// A step of a macro command failed, and the steps executed before it were undone.
+    RolledBack(Box<MacroReport>),
+    /// Validation rejected the state reached by the commands, for the given reasons. Nothing was changed.
+    Rejected(Vec<String>),
+    /// The history contains no command to undo.
+    NothingToUndo,
+    /// No command was undone since the last command was issued.
+    NothingToRedo,
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CommandError::UnknownCommand(ref name)  => write!(f, "unknown command: '{}'", name),
+            CommandError::UnknownDevice(ref id)     => write!(f, "unknown device: '{}'", id),
+            CommandError::InvalidArguments { ref command, ref message } => {
+                write!(f, "invalid arguments for {}: {}", command, message)
+            },
+            CommandError::Failed(ref reason)        => write!(f, "{}", reason),
+            CommandError::RolledBack(ref report)    => write!(f, "{}", report),
+            CommandError::Rejected(ref reasons)     => write!(f, "rejected: {}", reasons.join("; ")),
+            CommandError::NothingToUndo             => write!(f, "nothing to undo"),
+            CommandError::NothingToRedo             => write!(f, "nothing to redo"),
+        }
+    }
+}
+
+impl Error for CommandError {}
+
+/// What a command did to a light.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Outcome {
+    /// The light was turned on.
+    TurnedOn,
+    /// The light was turned off.
+    TurnedOff,
+    /// The brightness of the light was set, in percent.
+    Dimmed(u8),
+    /// The light was put back in a previous state.
+    Restored(Light),
+    /// A macro command was executed or undone.
+    Macro(MacroOutcome),
+}
+
+impl From<MacroOutcome> for Outcome {
+    fn from(outcome: MacroOutcome) -> Outcome {
+        Outcome::Macro(outcome)
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Outcome::TurnedOn               => write!(f, "light turned on"),
+            Outcome::TurnedOff              => write!(f, "light turned off"),
+            Outcome::Dimmed(brightness)     => write!(f, "light dimmed to {}%", brightness),
+            Outcome::Restored(state)        => write!(f, "light restored: {}", state),
+            Outcome::Macro(ref outcome)     => write!(f, "{}", outcome),
+        }
+    }
+}
+
+/// The command interface. Commands report what they did to their receiver as an outcome of type `O`, which defaults
+/// to the `Outcome` of the commands acting on a light.
+pub trait Command<O = Outcome> {
+    /// The name of the command, as it would be issued (for instance `DIM 40`).
+    fn name(&self) -> String;
+    /// Executes the command, storing whatever is needed to reverse it. A failing command must leave the receiver
+    /// unchanged.
+    fn execute(&mut self) -> Result<O>;
+    /// Reverses the effect of the last execution of the command.
+    fn unexecute(&mut self) -> Result<O>;
+}
+
+/// The object the handle
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Light {
+    on: bool,
+    brightness: u8,
+}
+
+impl Light {
+    /// Creates a light which is off, at full brightness once turned on.
+    pub fn new() -> Light {
+        Light {
+            on: false,
+            brightness: 100,
+        }
+    }
+
+    /// Creates a light in the given state. The brightness is capped at 100%.
+    pub fn from_state(on: bool, brightness: u8) -> Light {
+        Light {
+            on,
+            brightness: brightness.min(100),
+        }
+    }
+
+    /// Whether the light is on.
+    pub fn is_on(&self) -> bool {
+        self.on
+    }
+
+    /// The brightness of the light when it is on, in percent.
+    pub fn brightness(&self) -> u8 {
+        self.brightness
+    }
+
+    /// Turns the light on.
+    pub fn turn_on(&mut self) -> Outcome {
+        self.on = true;
+        Outcome::TurnedOn
+    }
+
+    /// Turns the light off.
+    pub fn turn_off(&mut self) -> Outcome {
+        self.on = false;
+        Outcome::TurnedOff
+    }
+
+    /// Turns the light on if it is off, and off if it is on.
+    pub fn toggle(&mut self) -> Outcome {
+        if self.on { self.turn_off() } else { self.turn_on() }
+    }
+
+    /// Sets the brightness, in percent. Only a light which is on can be dimmed.
+    pub fn dim(&mut self, brightness: u8) -> Result<Outcome> {
+        if !self.on {
+            return Err(CommandError::Failed(String::from("cannot dim a light which is off")));
+        }
+        if brightness > 100 {
+            return Err(CommandError::Failed(format!("invalid brightness {}%, at most 100% is allowed", brightness)));
+        }
+        self.brightness = brightness;
+        Ok(Outcome::Dimmed(brightness))
+    }
+
+    /// Restores a previous state of the light.
+    pub fn restore(&mut self, state: Light) -> Outcome {
+        *self = state;
+        Outcome::Restored(state)
+    }
+}
+
+impl Default for Light {
+    fn default() -> Self {
+        Light::new()
+    }
+}
+
+impl fmt::Display for Light {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.on {
+            true    => write!(f, "on at {}%", self.brightness),
+            false   => write!(f, "off"),
+        }
+    }
+}
+
+/// A light shared between the commands acting on it.
+pub type SharedLight = Rc<RefCell<Light>>;
+
+/// Reverts a light to the state it had before a command was executed.
+fn revert(light: &SharedLight, previous: &mut Option<Light>) -> Result<Outcome> {
+    match previous.take() {
+        Some(state) => Ok(light.borrow_mut().restore(state)),
+        None        => Err(CommandError::NothingToUndo),
+    }
+}
+
+/// A concrete command
+pub struct LightOnCommand {
+    light: SharedLight,
+    previous: Option<Light>,
+}
+
+impl LightOnCommand {
+    /// Creates a command turning on the given light.
+    pub fn new(light: SharedLight) -> Self {
+        LightOnCommand {
+            light,
+            previous: None,
+        }
+    }
+}
+
+impl Command for LightOnCommand {
+    fn name(&self) -> String {
+        String::from("ON")
+    }
+
+    fn execute(&mut self) -> Result<Outcome> {
+        let mut light = self.light.borrow_mut();
+        self.previous = Some(*light);
+        Ok(light.turn_on())
+    }
+
+    fn unexecute(&mut self) -> Result<Outcome> {
+        revert(&self.light, &mut self.previous)
+    }
+}
+
+/// Another concrete command
+pub struct LightOffCommand {
+    light: SharedLight,
+    previous: Option<Light>,
+}
+
+impl LightOffCommand {
+    /// Creates a command turning off the given light.
+    pub fn new(light: SharedLight) -> Self {
+        LightOffCommand {
+            light,
+            previous: None,
+        }
+    }
+}
+
+impl Command for LightOffCommand {
+    fn name(&self) -> String {
+        String::from("OFF")
+    }
+
+    fn execute(&mut self) -> Result<Outcome> {
+        let mut light = self.light.borrow_mut();
+        self.previous = Some(*light);
+        Ok(light.turn_off())
+    }
+
+    fn unexecute(&mut self) -> Result<Outcome> {
+        revert(&self.light, &mut self.previous)
+    }
+}
+
+/// A command switching a light to the opposite state
+pub struct ToggleCommand {
+    light: SharedLight,
+    previous: Option<Light>,
+}
+
+impl ToggleCommand {
+    /// Creates a command toggling the given light.
+    pub fn new(light: SharedLight) -> Self {
+        ToggleCommand {
+            light,
+            previous: None,
+        }
+    }
+}
+
+impl Command for ToggleCommand {
+    fn name(&self) -> String {
+        String::from("TOGGLE")
+    }
+
+    fn execute(&mut self) -> Result<Outcome> {
+        let mut light = self.light.borrow_mut();
+        self.previous = Some(*light);
+        Ok(light.toggle())
+    }
+
+    fn unexecute(&mut self) -> Result<Outcome> {
+        revert(&self.light, &mut self.previous)
+    }
+}
+
+/// A command changing the brightness of a light
+pub struct DimCommand {
+    light: SharedLight,
+    brightness: u8,
+    previous: Option<Light>,
+}
+
+impl DimCommand {
+    /// Creates a command setting the brightness of the given light, in percent.
+    pub fn new(light: SharedLight, brightness: u8) -> Self {
+        DimCommand {
+            light,
+            brightness,
+            previous: None,
+        }
+    }
+}
+
+impl Command for DimCommand {
+    fn name(&self) -> String {
+        format!("DIM {}", self.brightness)
+    }
+
+    fn execute(&mut self) -> Result<Outcome> {
+        let mut light = self.light.borrow_mut();
+        let previous = *light;
+        let result = light.dim(self.brightness)?;
+        self.previous = Some(previous);
+        Ok(result)
+    }
+
+    fn unexecute(&mut self) -> Result<Outcome> {
+        revert(&self.light, &mut self.previous)
+    }
+}
+
+
+/// A switch controlling the light. Executed commands are kept in a history in order to be undone, and undone commands
+/// are kept until a new command is issued in order to be redone.
+pub struct Switch {
+    light: SharedLight,
+    registry: Registry,
+    history: VecDeque<Box<dyn Command>>,
+    undone: Vec<Box<dyn Command>>,
+    limit: Option<usize>,
+}
+
+impl Switch {
+    /// Creates a switch with an empty, unbounded history.
+    pub fn new() -> Switch {
+        Switch {
+            light: Rc::new(RefCell::new(Light::new())),
+            registry: Registry::standard(),
+            history: VecDeque::new(),
+            undone: Vec::new(),
+            limit: None,
+        }
+    }
+
+    /// Creates a switch remembering at most `limit` commands. The oldest commands are forgotten first.
+    pub fn with_history_limit(limit: usize) -> Switch {
+        Switch {
+            limit: Some(limit),
+            ..Switch::new()
+        }
+    }
+
+    /// The current state of the light.
+    pub fn light(&self) -> Light {
+        *self.light.borrow()
+    }
+
+    /// The light controlled by the switch, to create commands acting on it.
+    pub fn receiver(&self) -> SharedLight {
+        Rc::clone(&self.light)
+    }
+
+    /// The registry creating the commands issued by name.
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    /// The registry creating the commands issued by name, to register more commands.
+    pub fn registry_mut(&mut self) -> &mut Registry {
+        &mut self.registry
+    }
+
+    /// Executes a command given by its name and arguments (such as `DIM 40`) and records it in the history.
+    pub fn execute_command(&mut self, cmd: &str) -> Result<Outcome> {
+        let command = self.registry.create(cmd, &self.light)?;
+        self.execute(command)
+    }
+
+    /// Executes a command and records it in the history. This discards the commands that were undone. Failing
+    /// commands are not recorded.
+    pub fn execute(&mut self, mut command: Box<dyn Command>) -> Result<Outcome> {
+        let result = command.execute()?;
+        self.undone.clear();
+        self.record(command);
+        Ok(result)
+    }
+
+    /// Undoes the last command of the history. If undoing fails, the command stays in the history.
+    pub fn undo(&mut self) -> Result<Outcome> {
+        let mut command = self.history.pop_back().ok_or(CommandError::NothingToUndo)?;
+        match command.unexecute() {
+            Ok(result)  => {
+                self.undone.push(command);
+                Ok(result)
+            },
+            Err(err)    => {
+                self.history.push_back(command);
+                Err(err)
+            },
+        }
+    }
+
+    /// Executes again the last command that was undone. If it fails, it can still be redone later.
+    pub fn redo(&mut self) -> Result<Outcome> {
+        let mut command = self.undone.pop().ok_or(CommandError::NothingToRedo)?;
+        match command.execute() {
+            Ok(result)  => {
+                self.record(command);
+                Ok(result)
+            },
+            Err(err)    => {
+                self.undone.push(command);
+                Err(err)
+            },
+        }
+    }
+
+    /// Whether there is a command to undo.
+    pub fn can_undo(&self) -> bool {
+        !self.history.is_empty()
+    }
+
+    /// Whether there is a command to redo.
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    /// The number of commands that can be undone.
+    pub fn history_len(&self) -> usize {
+        self.history.len()
+    }
+
+    /// The names of the commands that can be undone, oldest first.
+    pub fn history(&self) -> Vec<String> {
+        self.history.iter().map(|command| command.name()).collect()
+    }
+
+    /// Puts the light in the given state and forgets all commands.
+    pub fn reset(&mut self, light: Light) {
+        *self.light.borrow_mut() = light;
+        self.history.clear();
+        self.undone.clear();
+    }
+
+    /// Appends a command to the history, forgetting the oldest ones beyond the limit.
+    fn record(&mut self, command: Box<dyn Command>) {
+        self.history.push_back(command);
+        if let Some(limit) = self.limit {
+            while self.history.len() > limit {
+                self.history.pop_front();
+            }
+        }
+    }
+}
+
+impl Default for Switch {
+    fn default() -> Self {
+        Switch::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command() {
+        let mut switch = Switch::new();
+        assert_eq!(switch.execute_command("ON").unwrap().to_string(), "light turned on");
+        assert_eq!(switch.execute_command("OFF").unwrap().to_string(), "light turned off");
+        assert_eq!(switch.execute_command("ON").unwrap().to_string(), "light turned on");
+        assert_eq!(switch.execute_command("ON").unwrap().to_string(), "light turned on");
+    }
+
+    #[test]
+    fn test_toggle_and_dim() {
+        let mut switch = Switch::new();
+        assert_eq!(switch.execute_command("TOGGLE"), Ok(Outcome::TurnedOn));
+        assert_eq!(switch.execute_command("DIM 40").unwrap().to_string(), "light dimmed to 40%");
+        assert_eq!(switch.execute_command("TOGGLE"), Ok(Outcome::TurnedOff));
+        assert_eq!(switch.execute_command("TOGGLE"), Ok(Outcome::TurnedOn));
+        assert_eq!(switch.light().to_string(), "on at 40%");
+    }
+
+    #[test]
+    fn test_wrong_command() {
+        let mut switch = Switch::new();
+        assert_eq!(switch.execute_command("Random_command"),
+                   Err(CommandError::UnknownCommand("Random_command".to_string())));
+        assert_eq!(switch.execute_command("DIM").unwrap_err().to_string(),
+                   "invalid arguments for DIM: missing brightness");
+        assert!(!switch.can_undo());
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut switch = Switch::new();
+        assert_eq!(switch.execute(Box::new(DimCommand::new(switch.receiver(), 40))),
+                   Err(CommandError::Failed("cannot dim a light which is off".to_string())));
+        assert!(!switch.can_undo());
+        switch.execute_command("ON").unwrap();
+        let dim = DimCommand::new(switch.receiver(), 40);
+        assert_eq!(switch.execute(Box::new(dim)), Ok(Outcome::Dimmed(40)));
+        assert_eq!(switch.light().to_string(), "on at 40%");
+
+        assert_eq!(switch.undo().unwrap().to_string(), "light restored: on at 100%");
+        assert_eq!(switch.undo(), Ok(Outcome::Restored(Light::new())));
+        assert_eq!(switch.undo(), Err(CommandError::NothingToUndo));
+        assert_eq!(switch.light(), Light::new());
+
+        assert_eq!(switch.redo(), Ok(Outcome::TurnedOn));
+        assert!(switch.light().is_on());
+        assert!(switch.can_redo());
+
+        // A new command discards the commands left to redo.
+        switch.execute_command("OFF").unwrap();
+        assert!(!switch.can_redo());
+        assert_eq!(switch.redo(), Err(CommandError::NothingToRedo));
+        assert_eq!(switch.history_len(), 2);
+        switch.undo().unwrap();
+        assert_eq!(switch.light().brightness(), 100);
+        assert!(switch.light().is_on());
+    }
+
+    #[test]
+    fn test_history_limit() {
+        let mut switch = Switch::with_history_limit(2);
+        switch.execute_command("ON").unwrap();
+        switch.execute(Box::new(DimCommand::new(switch.receiver(), 10))).unwrap();
+        switch.execute_command("OFF").unwrap();
+        assert_eq!(switch.history_len(), 2);
+
+        assert!(switch.undo().is_ok());
+        assert!(switch.undo().is_ok());
+        assert!(!switch.can_undo());
+        // The first command was forgotten, the light stays on.
+        assert_eq!(switch.light().to_string(), "on at 100%");
+    }
+}
@@ -0,0 +1,180 @@
+//! Registry of named commands.
+//!
+//! Commands are issued by name, followed by their arguments, for instance `DIM 40`. A `Registry` maps every name to a
+//! factory parsing the arguments and creating the concrete command acting on a light. Unknown names and invalid
+//! arguments are reported as errors rather than panicking, and the registry documents the commands it knows about.
+
+use std::fmt;
+
+use super::{Command, CommandError, DimCommand, LightOffCommand, LightOnCommand, Result, SharedLight, ToggleCommand};
+
+/// Creates a command from its arguments, acting on the given light.
+pub type Factory = Box<dyn Fn(&[&str], &SharedLight) -> Result<Box<dyn Command>>>;
+
+/// A command known to a registry.
+pub struct CommandSpec {
+    name: String,
+    usage: String,
+    help: String,
+    factory: Factory,
+}
+
+impl CommandSpec {
+    /// The name of the command.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The arguments of the command, such as `<brightness>`.
+    pub fn usage(&self) -> &str {
+        &self.usage
+    }
+
+    /// What the command does.
+    pub fn help(&self) -> &str {
+        &self.help
+    }
+}
+
+/// Renders the command as `DIM <brightness>: sets the brightness of the light`.
+impl fmt::Display for CommandSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.usage.is_empty() {
+            true    => write!(f, "{}: {}", self.name, self.help),
+            false   => write!(f, "{} {}: {}", self.name, self.usage, self.help),
+        }
+    }
+}
+
+/// Maps command names to the factories creating them.
+pub struct Registry {
+    commands: Vec<CommandSpec>,
+}
+
+impl Registry {
+    /// Creates a registry without any command.
+    pub fn new() -> Registry {
+        Registry {
+            commands: Vec::new(),
+        }
+    }
+
+    /// Creates a registry containing the commands acting on a light: `ON`, `OFF`, `TOGGLE` and `DIM <brightness>`.
+    pub fn standard() -> Registry {
+        let mut registry = Registry::new();
+        registry.register("ON", "", "turns the light on", |args, light| {
+            no_arguments("ON", args)?;
+            Ok(Box::new(LightOnCommand::new(light.clone())))
+        });
+        registry.register("OFF", "", "turns the light off", |args, light| {
+            no_arguments("OFF", args)?;
+            Ok(Box::new(LightOffCommand::new(light.clone())))
+        });
+        registry.register("TOGGLE", "", "turns the light on if it is off, and off if it is on", |args, light| {
+            no_arguments("TOGGLE", args)?;
+            Ok(Box::new(ToggleCommand::new(light.clone())))
+        });
+        registry.register("DIM", "<brightness>", "sets the brightness of the light, from 0 to 100%", |args, light| {
+            let brightness = match *args {
+                [value]     => value.trim_end_matches('%').parse().ok().filter(|&value: &u8| value <= 100)
+                    .ok_or_else(|| invalid("DIM", &format!("invalid brightness '{}', expected 0 to 100", value)))?,
+                []          => return Err(invalid("DIM", "missing brightness")),
+                _           => return Err(invalid("DIM", "expected a single brightness")),
+            };
+            Ok(Box::new(DimCommand::new(light.clone(), brightness)))
+        });
+        registry
+    }
+
+    /// Registers a command, replacing any command registered under the same name.
+    pub fn register<F>(&mut self, name: &str, usage: &str, help: &str, factory: F)
+        where F: Fn(&[&str], &SharedLight) -> Result<Box<dyn Command>> + 'static {
+        self.commands.retain(|spec| !spec.name.eq_ignore_ascii_case(name));
+        self.commands.push(CommandSpec {
+            name: name.to_string(),
+            usage: usage.to_string(),
+            help: help.to_string(),
+            factory: Box::new(factory),
+        });
+    }
+
+    /// Returns the command registered under a name, ignoring case.
+    pub fn get(&self, name: &str) -> Option<&CommandSpec> {
+        self.commands.iter().find(|spec| spec.name.eq_ignore_ascii_case(name))
+    }
+
+    /// The names of the registered commands, in the order in which they were registered.
+    pub fn names(&self) -> Vec<&str> {
+        self.commands.iter().map(|spec| spec.name.as_str()).collect()
+    }
+
+    /// The help text of all commands, one per line.
+    pub fn help(&self) -> String {
+        self.commands.iter().map(|spec| format!("{}\n", spec)).collect()
+    }
+
+    /// Creates the command issued by a line such as `DIM 40`.
+    pub fn create(&self, line: &str, light: &SharedLight) -> Result<Box<dyn Command>> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let (name, args) = match words.split_first() {
+            Some((name, args))  => (*name, args),
+            None                => return Err(CommandError::UnknownCommand(String::new())),
+        };
+        let spec = self.get(name).ok_or_else(|| CommandError::UnknownCommand(name.to_string()))?;
+        (spec.factory)(args, light)
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Registry::standard()
+    }
+}
+
+fn invalid(command: &str, message: &str) -> CommandError {
+    CommandError::InvalidArguments { command: command.to_string(), message: message.to_string() }
+}
+
+fn no_arguments(command: &str, args: &[&str]) -> Result<()> {
+    match args.is_empty() {
+        true    => Ok(()),
+        false   => Err(invalid(command, "no arguments expected")),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{Outcome, Switch};
+
+    #[test]
+    fn test_registry() {
+        let registry = Registry::standard();
+        assert_eq!(registry.names(), vec!["ON", "OFF", "TOGGLE", "DIM"]);
+        assert_eq!(registry.get("dim").unwrap().to_string(),
+                   "DIM <brightness>: sets the brightness of the light, from 0 to 100%");
+        assert!(registry.help().starts_with("ON: turns the light on\nOFF: turns the light off\n"));
+
+        let light = SharedLight::default();
+        assert_eq!(registry.create("dim 40%", &light).unwrap().name(), "DIM 40");
+        let error = |line: &str| registry.create(line, &light).err().unwrap().to_string();
+        assert_eq!(error("BLINK 3"), "unknown command: 'BLINK'");
+        assert_eq!(error(""), "unknown command: ''");
+        assert_eq!(error("ON now"), "invalid arguments for ON: no arguments expected");
+        assert_eq!(error("DIM 140"), "invalid arguments for DIM: invalid brightness '140', expected 0 to 100");
+        assert_eq!(error("DIM 4 0"), "invalid arguments for DIM: expected a single brightness");
+    }
+
+    #[test]
+    fn test_custom_command() {
+        let mut switch = Switch::new();
+        switch.registry_mut().register("NIGHT", "", "dims the light to 10%", |args, light| {
+            no_arguments("NIGHT", args)?;
+            Ok(Box::new(DimCommand::new(light.clone(), 10)))
+        });
+        switch.execute_command("ON").unwrap();
+        assert_eq!(switch.execute_command("night"), Ok(Outcome::Dimmed(10)));
+        assert_eq!(switch.registry().names().last(), Some(&"NIGHT"));
+    }
+}
@@ -0,0 +1,280 @@
+//! Deferred and repeated commands.
+//!
+//! A `Scheduler` is an invoker executing commands at a later time rather than when they are issued. Each command has
+//! a due time and optionally a repeat interval, and scheduling it returns a `TaskHandle` which can cancel it until it
+//! runs (or for good, if it repeats).
+//!
+//! The scheduler does not read the time of the system: it keeps a `ManualClock`, measured from midnight of the first
+//! day, which only moves when `advance` or `advance_to` is called. Commands due in between are executed in order of
+//! their due time, and in the order they were scheduled for equal due times. This makes schedules deterministic, and
+//! lets the caller drive the clock from a real timer if needed.
+
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::fmt;
+use std::rc::Rc;
+use std::time::Duration;
+
+use super::{Command, Outcome, Result};
+use crate::clock::{Clock, ManualClock};
+
+/// The length of a day, to repeat commands daily.
+pub const DAY: Duration = Duration::from_secs(24 * 3600);
+
+/// The time of the first day at the given hour and minute.
+pub fn time_of_day(hours: u64, minutes: u64) -> Duration {
+    Duration::from_secs(hours * 3600 + minutes * 60)
+}
+
+/// Allows to cancel a scheduled command. Dropping the handle does not cancel the command.
+#[derive(Clone, Debug)]
+pub struct TaskHandle {
+    id: usize,
+    cancelled: Rc<Cell<bool>>,
+}
+
+impl TaskHandle {
+    /// The identifier of the task, as found in executions.
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Cancels the command. It is not executed anymore, even if it is due.
+    pub fn cancel(&self) {
+        self.cancelled.set(true);
+    }
+
+    /// Whether the command was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.get()
+    }
+}
+
+/// The execution of a scheduled command, reporting an outcome of type `O`.
+#[derive(Debug, PartialEq)]
+pub struct Execution<O = Outcome> {
+    /// The identifier of the task.
+    pub task: usize,
+    /// The time at which the command was executed.
+    pub at: Duration,
+    /// The name of the command.
+    pub command: String,
+    /// The result of the command.
+    pub result: Result<O>,
+}
+
+/// Renders the execution as `day 0 23:00 OFF: light turned off`, where days are counted from 0.
+impl<O> fmt::Display for Execution<O> where O: fmt::Display {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let minutes = self.at.as_secs() / 60;
+        write!(f, "day {} {:02}:{:02} {}: ", minutes / (24 * 60), minutes / 60 % 24, minutes % 60, self.command)?;
+        match self.result {
+            Ok(ref result)  => write!(f, "{}", result),
+            Err(ref err)    => write!(f, "failed: {}", err),
+        }
+    }
+}
+
+/// A command waiting in the queue.
+struct Task<O> {
+    due: Duration,
+    sequence: usize,
+    id: usize,
+    interval: Option<Duration>,
+    command: Box<dyn Command<O>>,
+    cancelled: Rc<Cell<bool>>,
+}
+
+// Tasks are ordered such that the earliest one is at the top of the (max-)heap.
+impl<O> Ord for Task<O> {
+    fn cmp(&self, other: &Task<O>) -> Ordering {
+        (other.due, other.sequence).cmp(&(self.due, self.sequence))
+    }
+}
+
+impl<O> PartialOrd for Task<O> {
+    fn partial_cmp(&self, other: &Task<O>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<O> PartialEq for Task<O> {
+    fn eq(&self, other: &Task<O>) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<O> Eq for Task<O> {}
+
+/// An invoker executing commands at given times of a virtual clock. The commands report outcomes of type `O`.
+pub struct Scheduler<O = Outcome> {
+    clock: ManualClock,
+    queue: BinaryHeap<Task<O>>,
+    tasks: usize,
+    sequence: usize,
+}
+
+impl<O> Scheduler<O> {
+    /// Creates a scheduler whose clock shows midnight of the first day.
+    pub fn new() -> Scheduler<O> {
+        Scheduler {
+            clock: ManualClock::default(),
+            queue: BinaryHeap::new(),
+            tasks: 0,
+            sequence: 0,
+        }
+    }
+
+    /// The time shown by the virtual clock.
+    pub fn now(&self) -> Duration {
+        self.clock.now()
+    }
+
+    /// The virtual clock, which can be shared with the objects reading the time of the schedule. Moving it directly
+    /// rather than with `advance` leaves the commands due in the meantime for the next advance.
+    pub fn clock(&self) -> &ManualClock {
+        &self.clock
+    }
+
+    /// The number of commands waiting to be executed, excluding cancelled ones.
+    pub fn pending(&self) -> usize {
+        self.queue.iter().filter(|task| !task.cancelled.get()).count()
+    }
+
+    /// Schedules a command at the given time. A time in the past executes it on the next advance of the clock.
+    pub fn schedule_at<C>(&mut self, due: Duration, command: C) -> TaskHandle where C: Command<O> + 'static {
+        self.push(due, None, Box::new(command))
+    }
+
+    /// Schedules a command after a delay.
+    pub fn schedule_in<C>(&mut self, delay: Duration, command: C) -> TaskHandle where C: Command<O> + 'static {
+        self.push(self.now() + delay, None, Box::new(command))
+    }
+
+    /// Schedules a command at the given time, and then repeatedly after every interval.
+    ///
+    /// # Panics
+    /// Panics if the interval is zero.
+    pub fn schedule_every<C>(&mut self, first: Duration, interval: Duration, command: C) -> TaskHandle
+        where C: Command<O> + 'static {
+        assert!(!interval.is_zero(), "a repeat interval cannot be zero");
+        self.push(first, Some(interval), Box::new(command))
+    }
+
+    /// Moves the clock forward and executes the commands due in the meantime.
+    pub fn advance(&mut self, by: Duration) -> Vec<Execution<O>> {
+        self.advance_to(self.now() + by)
+    }
+
+    /// Moves the clock to the given time, if it is in the future, and executes the commands due until then. A
+    /// repeating command is executed once for every interval elapsed.
+    pub fn advance_to(&mut self, time: Duration) -> Vec<Execution<O>> {
+        let mut executions = Vec::new();
+        while self.queue.peek().is_some_and(|task| task.due <= time) {
+            let mut task = self.queue.pop().unwrap();
+            if task.cancelled.get() {
+                continue;
+            }
+            self.clock.set(self.now().max(task.due));
+            executions.push(Execution {
+                task: task.id,
+                at: self.now(),
+                command: task.command.name(),
+                result: task.command.execute(),
+            });
+            if let Some(interval) = task.interval {
+                task.due += interval;
+                task.sequence = self.next_sequence();
+                self.queue.push(task);
+            }
+        }
+        self.clock.set(self.now().max(time));
+        executions
+    }
+
+    fn push(&mut self, due: Duration, interval: Option<Duration>, command: Box<dyn Command<O>>) -> TaskHandle {
+        self.tasks += 1;
+        let handle = TaskHandle {
+            id: self.tasks,
+            cancelled: Rc::new(Cell::new(false)),
+        };
+        let sequence = self.next_sequence();
+        self.queue.push(Task { due, sequence, id: handle.id, interval, command, cancelled: handle.cancelled.clone() });
+        handle
+    }
+
+    fn next_sequence(&mut self) -> usize {
+        self.sequence += 1;
+        self.sequence
+    }
+}
+
+impl<O> Default for Scheduler<O> {
+    fn default() -> Self {
+        Scheduler::new()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{DimCommand, LightOffCommand, LightOnCommand, Switch};
+
+    #[test]
+    fn test_scheduler() {
+        let switch = Switch::new();
+        let mut scheduler = Scheduler::new();
+        let evening = scheduler.schedule_every(time_of_day(18, 30), DAY, LightOnCommand::new(switch.receiver()));
+        scheduler.schedule_every(time_of_day(23, 0), DAY, LightOffCommand::new(switch.receiver()));
+        scheduler.schedule_at(time_of_day(18, 30), DimCommand::new(switch.receiver(), 60));
+        assert_eq!(scheduler.pending(), 3);
+
+        assert!(scheduler.advance_to(time_of_day(18, 0)).is_empty());
+        assert!(!switch.light().is_on());
+
+        let executions = scheduler.advance(Duration::from_secs(3600));
+        let lines: Vec<String> = executions.iter().map(|execution| execution.to_string()).collect();
+        assert_eq!(lines, vec!["day 0 18:30 ON: light turned on", "day 0 18:30 DIM 60: light dimmed to 60%"]);
+        assert_eq!(scheduler.now(), time_of_day(19, 0));
+        assert_eq!(switch.light().to_string(), "on at 60%");
+
+        let executions = scheduler.advance_to(DAY + time_of_day(12, 0));
+        assert_eq!(executions.iter().map(|execution| execution.at).collect::<Vec<_>>(), vec![time_of_day(23, 0)]);
+        assert!(!switch.light().is_on());
+
+        // Every day, the light goes on at 18:30 and off at 23:00, until the evening task is cancelled.
+        let executions = scheduler.advance(DAY);
+        let commands: Vec<&str> = executions.iter().map(|execution| execution.command.as_str()).collect();
+        assert_eq!(commands, vec!["ON", "OFF"]);
+        evening.cancel();
+        assert_eq!(scheduler.pending(), 1);
+        let executions = scheduler.advance(2 * DAY);
+        assert_eq!(executions.len(), 2);
+        assert!(executions.iter().all(|execution| execution.task != evening.id()));
+    }
+
+    #[test]
+    fn test_scheduler_failures() {
+        let switch = Switch::new();
+        let mut scheduler = Scheduler::new();
+        scheduler.advance_to(time_of_day(8, 0));
+        scheduler.schedule_in(Duration::from_secs(60), DimCommand::new(switch.receiver(), 150));
+        scheduler.schedule_in(Duration::from_secs(60), DimCommand::new(switch.receiver(), 10));
+        // Due in the past, executed on the next advance.
+        scheduler.schedule_at(time_of_day(7, 0), LightOnCommand::new(switch.receiver()));
+
+        let executions = scheduler.advance(Duration::from_secs(60));
+        assert_eq!(executions[0].to_string(), "day 0 08:00 ON: light turned on");
+        assert_eq!(executions[1].to_string(),
+                   "day 0 08:01 DIM 150: failed: invalid brightness 150%, at most 100% is allowed");
+        assert_eq!(executions[2].to_string(), "day 0 08:01 DIM 10: light dimmed to 10%");
+
+        let handle = scheduler.schedule_in(Duration::from_secs(60), LightOffCommand::new(switch.receiver()));
+        handle.cancel();
+        assert!(handle.is_cancelled());
+        assert!(scheduler.advance(DAY).is_empty());
+        assert_eq!(scheduler.pending(), 0);
+    }
+}
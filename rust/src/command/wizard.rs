@@ -0,0 +1,252 @@
+//! Staged commands applied at once.
+//!
+//! A `Wizard` collects commands over several steps without executing them. The pending changes can be previewed as a
+//! diff of the state of the receiver, discarded with `cancel()`, or applied with `finish()`.
+//!
+//! Commands are staged as factories building them for a given receiver. Previews and finishing build the commands for
+//! a detached clone of the receiver and run them on it, so the receiver itself never sees a partial change. Finishing
+//! is atomic: the state reached by the commands is checked by the validation rules of the wizard, and only replaces
+//! the state of the receiver if no command failed and no rule rejected it.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+use std::result;
+
+use super::{Command, CommandError, Result};
+
+/// A validation rule, returning why a state is rejected.
+pub type Rule<R> = Box<dyn Fn(&R) -> result::Result<(), String>>;
+
+/// A staged command, built for the receiver it is given.
+type Factory<R, O> = Box<dyn Fn(Rc<RefCell<R>>) -> Box<dyn Command<O>>>;
+
+/// What a wizard did when it was finished.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WizardOutcome {
+    /// The name of the wizard.
+    pub name: String,
+    /// The names of the commands applied.
+    pub commands: Vec<String>,
+}
+
+impl fmt::Display for WizardOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "wizard '{}' applied: {}", self.name, super::macro_command::list(&self.commands))
+    }
+}
+
+/// The changes pending in a wizard.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Preview<R> {
+    /// The names of the pending commands.
+    pub commands: Vec<String>,
+    /// The state of the receiver now.
+    pub before: R,
+    /// The state of the receiver once the commands are applied.
+    pub after: R,
+    /// Why the validation rules would reject the changes.
+    pub violations: Vec<String>,
+}
+
+impl<R> Preview<R> where R: fmt::Display {
+    /// Whether finishing the wizard would apply the changes.
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    /// The lines of the state which change, prefixed with `-` when removed and with `+` when added.
+    pub fn diff(&self) -> Vec<String> {
+        let (before, after) = (self.before.to_string(), self.after.to_string());
+        let removed = before.lines().filter(|line| !after.lines().any(|other| other == *line));
+        let added = after.lines().filter(|line| !before.lines().any(|other| other == *line));
+        removed.map(|line| format!("- {}", line)).chain(added.map(|line| format!("+ {}", line))).collect()
+    }
+}
+
+/// Renders the pending commands followed by the diff and the violations, one per line.
+impl<R> fmt::Display for Preview<R> where R: fmt::Display {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "pending: {}", super::macro_command::list(&self.commands))?;
+        let diff = self.diff();
+        match diff.is_empty() {
+            true    => writeln!(f, "no changes")?,
+            false   => diff.iter().try_for_each(|line| writeln!(f, "{}", line))?,
+        }
+        self.violations.iter().try_for_each(|violation| writeln!(f, "rejected: {}", violation))
+    }
+}
+
+/// A group of commands staged together.
+struct Step<R, O> {
+    title: String,
+    commands: Vec<Factory<R, O>>,
+}
+
+/// An invoker staging commands on a receiver until it is finished. The commands report outcomes of type `O`.
+pub struct Wizard<R, O> {
+    name: String,
+    receiver: Rc<RefCell<R>>,
+    steps: Vec<Step<R, O>>,
+    rules: Vec<Rule<R>>,
+}
+
+impl<R, O> Wizard<R, O> where R: Clone + fmt::Display + 'static {
+    /// Creates a wizard staging commands acting on the given receiver.
+    pub fn new<S>(name: S, receiver: Rc<RefCell<R>>) -> Wizard<R, O> where S: Into<String> {
+        Wizard {
+            name: name.into(),
+            receiver,
+            steps: Vec::new(),
+            rules: Vec::new(),
+        }
+    }
+
+    /// Adds a validation rule, which must accept the state reached by the commands for them to be applied.
+    pub fn rule<F>(mut self, rule: F) -> Self where F: Fn(&R) -> result::Result<(), String> + 'static {
+        self.rules.push(Box::new(rule));
+        self
+    }
+
+    /// Starts a new step. Commands staged afterwards belong to it.
+    pub fn step<S>(&mut self, title: S) where S: Into<String> {
+        self.steps.push(Step { title: title.into(), commands: Vec::new() });
+    }
+
+    /// Stages a command in the current step, starting an untitled step if there is none. The command is given as a
+    /// function building it for a receiver, such as `LightOnCommand::new`.
+    pub fn stage<F, C>(&mut self, factory: F) where F: Fn(Rc<RefCell<R>>) -> C + 'static, C: Command<O> + 'static {
+        if self.steps.is_empty() {
+            self.step("");
+        }
+        let factory: Factory<R, O> = Box::new(move |receiver| Box::new(factory(receiver)));
+        self.steps.last_mut().unwrap().commands.push(factory);
+    }
+
+    /// Discards the current step and the commands staged in it, going back to the previous step. Returns the title
+    /// of the discarded step.
+    pub fn back(&mut self) -> Option<String> {
+        self.steps.pop().map(|step| step.title)
+    }
+
+    /// The titles of the steps.
+    pub fn steps(&self) -> Vec<&str> {
+        self.steps.iter().map(|step| step.title.as_str()).collect()
+    }
+
+    /// The names of the staged commands, in the order in which they would be executed.
+    pub fn pending(&self) -> Vec<String> {
+        self.commands(&self.detached()).iter().map(|command| command.name()).collect()
+    }
+
+    /// Runs the staged commands on a detached clone of the receiver to show their effect. The receiver is unchanged.
+    pub fn preview(&self) -> Result<Preview<R>> {
+        let after = self.run()?;
+        let before = self.receiver.borrow().clone();
+        Ok(Preview { commands: self.pending(), violations: self.violations(&after), before, after })
+    }
+
+    /// Discards all staged commands without executing them.
+    pub fn cancel(&mut self) {
+        self.steps.clear();
+    }
+
+    /// Applies all staged commands if none fails and the validation rules accept the result. Otherwise nothing is
+    /// changed, and the commands stay staged such that they can be fixed.
+    pub fn finish(&mut self) -> Result<WizardOutcome> {
+        let after = self.run()?;
+        let violations = self.violations(&after);
+        if !violations.is_empty() {
+            return Err(CommandError::Rejected(violations));
+        }
+        *self.receiver.borrow_mut() = after;
+        let commands = self.pending();
+        self.steps.clear();
+        Ok(WizardOutcome { name: self.name.clone(), commands })
+    }
+
+    /// A clone of the receiver, which commands can change without affecting the receiver.
+    fn detached(&self) -> Rc<RefCell<R>> {
+        Rc::new(RefCell::new(self.receiver.borrow().clone()))
+    }
+
+    /// Builds the staged commands for the given receiver.
+    fn commands(&self, receiver: &Rc<RefCell<R>>) -> Vec<Box<dyn Command<O>>> {
+        self.steps.iter().flat_map(|step| step.commands.iter()).map(|factory| factory(Rc::clone(receiver))).collect()
+    }
+
+    /// Executes the staged commands on a detached clone of the receiver, returning the state they lead to.
+    fn run(&self) -> Result<R> {
+        let receiver = self.detached();
+        self.commands(&receiver).iter_mut().try_for_each(|command| command.execute().map(|_| ()))?;
+        let after = receiver.borrow().clone();
+        Ok(after)
+    }
+
+    fn violations(&self, state: &R) -> Vec<String> {
+        self.rules.iter().filter_map(|rule| rule(state).err()).collect()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::home::{Device, Home, SetCommand, Setting};
+    use super::super::{DimCommand, Light, LightOnCommand, Outcome, Switch};
+
+    #[test]
+    fn test_wizard() {
+        let switch = Switch::new();
+        let mut wizard: Wizard<Light, Outcome> = Wizard::new("setup", switch.receiver());
+        wizard.step("power");
+        wizard.stage(LightOnCommand::new);
+        wizard.step("brightness");
+        wizard.stage(|light| DimCommand::new(light, 40));
+        assert_eq!(wizard.steps(), vec!["power", "brightness"]);
+
+        let preview = wizard.preview().unwrap();
+        assert_eq!(preview.to_string(), "pending: ON, DIM 40\n- off\n+ on at 40%\n");
+        assert_eq!(switch.light(), Light::new());
+
+        assert_eq!(wizard.back(), Some("brightness".to_string()));
+        wizard.stage(|light| DimCommand::new(light, 140));
+        assert!(wizard.finish().is_err());
+        assert_eq!(switch.light(), Light::new());
+
+        wizard.cancel();
+        assert!(wizard.pending().is_empty());
+        wizard.stage(LightOnCommand::new);
+        assert_eq!(wizard.finish().unwrap().to_string(), "wizard 'setup' applied: ON");
+        assert_eq!(switch.light().to_string(), "on at 100%");
+        assert!(wizard.pending().is_empty());
+    }
+
+    #[test]
+    fn test_wizard_validation() {
+        let mut home = Home::new();
+        home.add("heating", Device::Thermostat(19.0)).add("blinds", Device::Blinds(100));
+        let home = Rc::new(RefCell::new(home));
+        let mut wizard = Wizard::new("night", Rc::clone(&home)).rule(|home: &Home| {
+            match home.device("heating") {
+                Some(Device::Thermostat(setpoint)) if setpoint > 18.0   => Err(format!("{}°C is too warm", setpoint)),
+                _                                                       => Ok(()),
+            }
+        });
+        wizard.stage(|home| SetCommand::new(home, "blinds", Setting::Position(0)));
+        wizard.stage(|home| SetCommand::new(home, "heating", Setting::Setpoint(20.0)));
+
+        let preview = wizard.preview().unwrap();
+        assert!(!preview.is_valid());
+        assert_eq!(preview.diff(), vec!["- blinds: blinds 100% open", "- heating: thermostat set to 19.0°C",
+                                        "+ blinds: blinds 0% open", "+ heating: thermostat set to 20.0°C"]);
+        assert_eq!(wizard.finish(), Err(CommandError::Rejected(vec!["20°C is too warm".to_string()])));
+        assert_eq!(home.borrow().device("blinds"), Some(Device::Blinds(100)));
+
+        wizard.back();
+        wizard.stage(|home| SetCommand::new(home, "blinds", Setting::Position(0)));
+        wizard.stage(|home| SetCommand::new(home, "heating", Setting::Setpoint(17.0)));
+        assert!(wizard.finish().is_ok());
+        assert_eq!(home.borrow().device("heating"), Some(Device::Thermostat(17.0)));
+    }
+}
@@ -54,34 +54,45 @@
 //! # Known Uses
 //! - JDRAW - Grouping
 //! - JComponents in Swing: leafs are JLabel, JCheckbox, etc.
+//!
+//! # Example
+//! ```
+//! use patterns::composite::{CompositeGraphic, Ellipse, Graphic};
+//!
+//! let mut group = CompositeGraphic::new();
+//! group.add(Box::new(Ellipse));
+//! group.add(Box::new(Ellipse));
+//! assert_eq!(group.print(), "EllipseEllipse");
+//! ```
 
 
 /// A trait defining the (graphical) component.
-trait Graphic {
+pub trait Graphic {
     /// Prints the type of the graphic
     fn print(&self) -> String;
 }
 
 /// The composite.
-struct CompositeGraphic {
-    children: Vec<Box<Graphic>>,
+#[derive(Default)]
+pub struct CompositeGraphic {
+    children: Vec<Box<dyn Graphic>>,
 }
 
 impl CompositeGraphic {
     /// Constructor
-    fn new() -> Self {
+    pub fn new() -> Self {
         CompositeGraphic{
             children: Vec::new(),
         }
     }
 
     /// Add a component
-    fn add(&mut self, graphic: Box<Graphic>) {
+    pub fn add(&mut self, graphic: Box<dyn Graphic>) {
         self.children.push(graphic);
     }
 
     /// Remove the last added component
-    fn remove(&mut self) {
+    pub fn remove(&mut self) {
         self.children.pop();
     }
 }
@@ -98,7 +109,7 @@ impl Graphic for CompositeGraphic {
 
 
 /// Leaf
-struct Ellipse;
+pub struct Ellipse;
 
 impl Graphic for Ellipse {
     fn print(&self) -> String {
@@ -11,7 +11,7 @@
 //! On top of that, functionality can be incremented at runtime, since the complexity is not
 //! statically part of the object but can be added as it is needed.
 //!
-//! # Praticipants
+//! # Participants
 //! - `Shape`: defines the interface of objects that can have dynamically added responsabilities.
 //!   This should be lightweight since several components can be encapsulated into each other.
 //! - `Circle`: this is a concrete component implementing the `Shape` component interface. It
@@ -36,15 +36,24 @@
 //! - ScrollPane in Java
 //! - JDRAW - Border, FixedPosition, Blinking, etc.
 //! - Output streams in Java
+//!
+//! # Example
+//! ```
+//! use patterns::decorator::{Circle, ColouredShape, Shape};
+//!
+//! let shape = ColouredShape::new("blue", Circle { radius: 1.0 });
+//! assert_eq!(shape.description(), "Circle of radius 1 which is coloured blue");
+//! ```
 
 
 /// A simple circle
-struct Circle {
-    radius: f32,
+pub struct Circle {
+    /// The radius of the circle
+    pub radius: f32,
 }
 
 /// A shape
-trait Shape {
+pub trait Shape {
     /// Give a description of the shape
     fn description(&self) -> String;
 }
@@ -55,8 +64,15 @@ impl Shape for Circle {
     }
 }
 
+/// Boxed shapes are shapes too, this allows to pick the decorators to apply at runtime.
+impl<T: Shape + ?Sized> Shape for Box<T> {
+    fn description(&self) -> String {
+        (**self).description()
+    }
+}
+
 /// Any coloured shape
-struct ColouredShape<T> {
+pub struct ColouredShape<T> {
     shape: T,
     colour: String,
 }
@@ -69,7 +85,7 @@ impl<T: Shape> Shape for ColouredShape<T> {
 
 impl<T> ColouredShape<T> where T: Shape {
     /// Constructor
-    fn new(colour: &str, shape: T) -> Self {
+    pub fn new(colour: &str, shape: T) -> Self {
         ColouredShape {
             shape,
             colour: colour.to_string(),
@@ -12,10 +12,10 @@
 //!
 //! # Participants
 //! - `Compiler`: the facade for all its components. It knows which subsystem classes are responsible for a request. It
-//!    then delegates client requests to appropriate subsystem objects.
+//!   then delegates client requests to appropriate subsystem objects.
 //! - `Linker`, `Parser`, `CodeGenerator` and `Optimiser`: subsystem classes implementing subsystem functionality. They
-//!    handle the work assigned by the façade object. They may have no knowledge of the façade (as in the implementation
-//!    below).
+//!   handle the work assigned by the façade object. They may have no knowledge of the façade (as in the implementation
+//!   below).
 //!
 //! # Modifications and Strategies
 //! The coupling between clients and the subsytem can be reduced even further by making the façade an abstract class (or
@@ -24,48 +24,66 @@
 //! # Known Uses
 //! - Compilers
 //! - Any library providing a simplified "general purpose" interface for a more complex underlying code base.
+//!
+//! # Example
+//! ```
+//! use patterns::facade::Compiler;
+//!
+//! let compiler = Compiler::new();
+//! assert!(compiler.run().ends_with("linking code"));
+//! ```
 
 /// A subsystem.
-struct Linker;
+#[derive(Default)]
+pub struct Linker;
 impl Linker {
-    fn run(&self) -> &str {
+    /// Links the generated code.
+    pub fn run(&self) -> &str {
         "linking code"
     }
 }
 
 /// Another subsystem.
-struct Parser;
+#[derive(Default)]
+pub struct Parser;
 impl Parser {
-    fn run(&self) -> &str {
+    /// Parses the source code.
+    pub fn run(&self) -> &str {
         "parsing source code"
     }
 }
 
 /// Another subsystem.
-struct Optimiser;
+#[derive(Default)]
+pub struct Optimiser;
 impl Optimiser {
-    fn run(&self) -> &str {
+    /// Optimises the generated code.
+    pub fn run(&self) -> &str {
         "optimising generate machine code"
     }
 }
 
 /// Another subsystem.
-struct CodeGenerator;
+#[derive(Default)]
+pub struct CodeGenerator;
 impl CodeGenerator {
-    fn run(&self) -> &str {
+    /// Generates machine code.
+    pub fn run(&self) -> &str {
         "generating machine code"
     }
 }
 
 /// The entire system. A facade for all its subsystem components.
-struct Compiler {
+#[derive(Default)]
+pub struct Compiler {
     parser: Parser,
     generator: CodeGenerator,
     optimiser: Optimiser,
     linker: Linker,
 }
 impl Compiler {
-    fn new() -> Compiler {
+    /// Creates a compiler with all its subsystems.
+    pub fn new() -> Compiler {
         Compiler {
             parser: Parser,
             generator: CodeGenerator,
@@ -74,7 +92,8 @@ impl Compiler {
         }
     }
 
-    fn run(&self) -> String {
+    /// Runs the whole compilation pipeline.
+    pub fn run(&self) -> String {
         format!("{}\n{}\n{}\n{}",
                 self.parser.run(),
                 self.generator.run(),
@@ -8,7 +8,7 @@
 //! # Participants
 //! - `Car`: the product to create in the "factory". It defines the interface for objects created in said "factory".
 //! - `Sedan`: the concrete product that implements the interface of the general product above.
-//! - `Factory`: the creator (factory) which declares the factory method, which returns an object of type `Car`. This
+//! - `CarFactory`: the creator (factory) which declares the factory method, which returns an object of type `Car`. This
 //!   this can also define a default `Car` object if this is necessary/appropriate.
 //! - `SedanFactory`: the class overriding the factory method from the abstract creator to return a specific instance
 //!   of a concrete product (`Sedan`).
@@ -20,6 +20,14 @@
 //! # Known Uses
 //! - Junit Test
 //!
+//! # Example
+//! ```
+//! use patterns::factory_method::{Car, CarFactory, SedanFactory};
+//!
+//! let car = SedanFactory.make_car();
+//! assert_eq!(car.get_type(), "Sedan");
+//! ```
+//!
 //! # Comments
 //! Note that using traits, the same result can be achieved by making sure all cars implement a `make_car` static method
 //! that returns a car of the type given by the class implementing the trait. This results in a factory method shared
@@ -28,7 +36,7 @@
 //! type of `Car`).
 
 /// A trait defining the behaviour of a car.
-trait Car {
+pub trait Car {
     /// Get the type of a car.
     ///
     /// # Returns
@@ -37,16 +45,16 @@ trait Car {
 }
 
 /// A trait defining the behaviour of a CarFactory. This is the factory.
-trait CarFactory {
+pub trait CarFactory {
     /// Make a car. This is the factory method
     ///
     /// # Returns
     /// A Car.
-    fn make_car(&self) -> Box<Car>;
+    fn make_car(&self) -> Box<dyn Car>;
 }
 
 /// A car of type sedan.
-struct Sedan;
+pub struct Sedan;
 
 impl Car for Sedan {
     fn get_type(&self) -> &'static str {
@@ -55,10 +63,10 @@ impl Car for Sedan {
 }
 
 /// A factory building sedans.
-struct SedanFactory;
+pub struct SedanFactory;
 
 impl CarFactory for SedanFactory {
-    fn make_car(&self) -> Box<Car> {
+    fn make_car(&self) -> Box<dyn Car> {
         Box::new(Sedan {})
     }
 }
@@ -67,13 +75,16 @@ impl CarFactory for SedanFactory {
 /// exposes the real type of the object to the client. This might however, not be a problem in some scenarios. In terms
 /// of performance, this is prefered, as no dynamic lookup is required on method calls on the objects created by the
 /// factory method.
-trait CarFactory2 {
+pub trait CarFactory2 {
+    /// The concrete car type built by the factory.
     type CarType: Car;
 
+    /// Make a car. This is the factory method
     fn make_car(&self) -> Self::CarType;
 }
 
-struct BetterSedanFactory;
+/// A factory building sedans through `CarFactory2`.
+pub struct BetterSedanFactory;
 
 impl CarFactory2 for BetterSedanFactory {
     type CarType = Sedan;
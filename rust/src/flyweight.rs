@@ -27,6 +27,19 @@
 //! One can additionally add an interface for the flyweight class such that the factory can create a variety of
 //! different flyweight objects (from different classes). Note that it is important that the client cannot build its
 //! own flyweight objects, or no shared state across clients will be possible.
+//!
+//! # Example
+//! ```
+//! use patterns::flyweight::{CheeseShop, Menu};
+//!
+//! let menu = Menu::new();
+//! let mut shop_1 = CheeseShop::new(&menu);
+//! let mut shop_2 = CheeseShop::new(&menu);
+//!
+//! shop_1.stock_cheese("brie", 3.0, 2.0);
+//! assert!(shop_2.sell("brie", 2.0).is_ok());
+//! assert!(shop_1.sell("brie", 1.0).is_err());
+//! ```
 
 use std::cell::RefCell;
 use std::collections::HashMap;
@@ -34,10 +47,12 @@ use std::fmt;
 use std::error::Error;
 use std::result;
 
-type Result<T> = result::Result<T, OutOfStockError>;
+/// Result of the operations on the shared inventory.
+pub type Result<T> = result::Result<T, OutOfStockError>;
 
+/// Error returned when a cheese is not on the menu or not available in the requested quantity.
 #[derive(Debug, PartialEq, Eq)]
-struct OutOfStockError;
+pub struct OutOfStockError;
 impl fmt::Display for OutOfStockError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "Out of stock")
@@ -48,7 +63,7 @@ impl Error for OutOfStockError {
         "Out of stock"
     }
 
-    fn cause(&self) -> Option<&Error> {
+    fn cause(&self) -> Option<&dyn Error> {
         None
     }
 }
@@ -56,17 +71,19 @@ impl Error for OutOfStockError {
 /// The flyweight factory, it is shared across all `CheeseShop`s. `CheeseShop`s can use its interface to add specific
 /// flyweight objects (`CheeseBrand`) to the menu. Note that this is not thread-safe as it uses a `RefCell`. In a
 /// multithreaded scenario, the use of mutexes would be required.
-struct Menu {
+pub struct Menu {
     items: RefCell<HashMap<String, CheeseBrand>>,
 }
 impl Menu {
-    fn new() -> Menu {
+    /// Creates an empty menu.
+    pub fn new() -> Menu {
         Menu {
             items: RefCell::new(HashMap::new()),
         }
     }
 
-    fn add(&self, name: &str, cost: f32, quantity: f32) {
+    /// Adds a cheese to the menu, or updates its cost and quantity if it is already present.
+    pub fn add(&self, name: &str, cost: f32, quantity: f32) {
         let cheese = CheeseBrand::new(name, cost, quantity);
         let mut items = self.items.borrow_mut();
         let entry = items.entry(String::from(name)).or_insert(cheese);
@@ -74,7 +91,8 @@ impl Menu {
         entry.quantity = quantity;
     }
 
-    fn sell(&self, name: &str, quantity: f32) -> Result<f32> {
+    /// Removes some quantity of a cheese from the inventory and returns its unit cost.
+    pub fn sell(&self, name: &str, quantity: f32) -> Result<f32> {
         let mut items = self.items.borrow_mut();
         match items.get_mut(name) {
             Some(ch)    => {
@@ -85,15 +103,21 @@ impl Menu {
         }
     }
 }
+impl Default for Menu {
+    fn default() -> Self {
+        Menu::new()
+    }
+}
 
 /// The flyweight objects. All cheesebrands (name, quantity, and cost) are shared across all `CheeseShop`s.
-struct CheeseBrand {
+pub struct CheeseBrand {
     name: String,
     cost: f32,
     quantity: f32,
 }
 impl CheeseBrand {
-    fn new(name: &str, cost: f32, quantity: f32) -> CheeseBrand {
+    /// Creates a cheese brand.
+    pub fn new(name: &str, cost: f32, quantity: f32) -> CheeseBrand {
         CheeseBrand {
             name: String::from(name),
             cost,
@@ -101,13 +125,29 @@ impl CheeseBrand {
         }
     }
 
-    fn reduce_quantity(&mut self, quantity: f32) -> Result<()> {
+    /// Removes some quantity of the cheese from the inventory.
+    pub fn reduce_quantity(&mut self, quantity: f32) -> Result<()> {
         if quantity > self.quantity {
             return Err(OutOfStockError);
         }
         self.quantity -= quantity;
         Ok(())
     }
+
+    /// The name of the cheese.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The unit cost of the cheese.
+    pub fn cost(&self) -> f32 {
+        self.cost
+    }
+
+    /// The quantity left in the inventory.
+    pub fn quantity(&self) -> f32 {
+        self.quantity
+    }
 }
 impl PartialEq for CheeseBrand {
     fn eq(&self, other: &CheeseBrand) -> bool {
@@ -120,13 +160,14 @@ impl Eq for CheeseBrand {}
 /// The class sharing the flyweight. This defines individual cheese shops that have their own extrinsic state (the
 /// number of cheese units sold and the total revenue made in this particular shop). However, it also shares a global
 /// inventory across all shops representing the intrinsic state of the shop.
-struct CheeseShop<'a> {
+pub struct CheeseShop<'a> {
     menu: &'a Menu,
     units_sold: f32,
     revenue: f32,
 }
 impl<'a> CheeseShop<'a> {
-    fn new(menu: &'a Menu) -> CheeseShop {
+    /// Creates a shop sharing the given menu.
+    pub fn new(menu: &'a Menu) -> CheeseShop<'a> {
         CheeseShop {
             menu,
             units_sold: 0_f32,
@@ -134,22 +175,26 @@ impl<'a> CheeseShop<'a> {
         }
     }
 
-    fn stock_cheese(&self, name: &str, cost: f32, quantity: f32) {
+    /// Adds a cheese to the shared menu.
+    pub fn stock_cheese(&self, name: &str, cost: f32, quantity: f32) {
         self.menu.add(name, cost, quantity);
     }
 
-    fn sell(&mut self, name: &str, quantity: f32) -> Result<()> {
+    /// Sells some quantity of a cheese from the shared menu.
+    pub fn sell(&mut self, name: &str, quantity: f32) -> Result<()> {
         let cost = self.menu.sell(name, quantity)?;
         self.units_sold += quantity;
         self.revenue += cost * quantity;
         Ok(())
     }
 
-    fn total_units_sold(&self) -> f32 {
+    /// The number of units this shop sold.
+    pub fn total_units_sold(&self) -> f32 {
         self.units_sold
     }
 
-    fn total_revenue(&self) -> f32 {
+    /// The revenue this shop made.
+    pub fn total_revenue(&self) -> f32 {
         self.revenue
     }
 }
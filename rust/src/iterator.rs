@@ -38,12 +38,35 @@
 //!
 //! # Known Uses
 //! Legit everywhere.
+//!
+//! # Example
+//! ```
+//! use patterns::iterator::CustomList;
+//!
+//! let list = CustomList::new("fibonacci");
+//! let numbers: Vec<u32> = list.into_iter().take(6).collect();
+//! assert_eq!(numbers, vec![1, 1, 2, 3, 5, 8]);
+//! ```
 
 /// The object to iterate over
-struct CustomList {
+pub struct CustomList {
     fib: Fibonacci,
     name: String,
 }
+impl CustomList {
+    /// Creates a list containing the Fibonacci sequence.
+    pub fn new(name: &str) -> CustomList {
+        CustomList {
+            fib: Fibonacci::new(),
+            name: String::from(name),
+        }
+    }
+
+    /// The name of the list.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
 impl IntoIterator for CustomList {
     type Item = u32;
     type IntoIter = Fibonacci;
@@ -52,10 +75,25 @@ impl IntoIterator for CustomList {
     }
 }
 
-struct Fibonacci {
+/// The concrete iterator, yielding the Fibonacci sequence.
+pub struct Fibonacci {
     current: u32,
     next: u32,
 }
+impl Fibonacci {
+    /// Creates an iterator starting at the beginning of the sequence.
+    pub fn new() -> Fibonacci {
+        Fibonacci {
+            current: 0,
+            next: 1,
+        }
+    }
+}
+impl Default for Fibonacci {
+    fn default() -> Self {
+        Fibonacci::new()
+    }
+}
 impl Iterator for Fibonacci {
     type Item = u32;
     fn next(&mut self) -> Option<Self::Item> {
@@ -0,0 +1,62 @@
+//! A collection of design patterns.
+//!
+//! Every pattern lives in its own module. Each module documents the theory behind the pattern and exposes two kinds of
+//! items:
+//! - the reusable abstractions of the pattern (mostly traits such as `Command`, `Observer` or `State`). These are
+//!   re-exported in the [`prelude`] module and form the supported interface of the crate.
+//! - demo types (such as `Light`, `Employee` or `CheeseShop`) implementing these abstractions. They illustrate how the
+//!   pattern is applied and are public so they can be used in examples and from the `patterns` binary.
+//!
+//! # Example
+//! ```
+//! use patterns::prelude::*;
+//! use patterns::strategy::{FastAlgorithm, SomeObject};
+//!
+//! let object = SomeObject::new(Box::new(FastAlgorithm));
+//! assert_eq!(object.run(), "very fast algorithm");
+//! ```
+
+#![deny(missing_docs)]
+
+pub mod factory_method;
+pub mod abstract_factory;
+pub mod prototype;
+pub mod singleton;
+pub mod composite;
+pub mod decorator;
+pub mod builder;
+pub mod proxy;
+pub mod command;
+pub mod observer;
+pub mod state;
+pub mod strategy;
+pub mod template_method;
+pub mod adapter;
+pub mod bridge;
+pub mod facade;
+pub mod flyweight;
+pub mod chain_of_responsibility;
+pub mod iterator;
+pub mod mediator;
+pub mod catalogue;
+pub mod clock;
+
+/// The reusable abstractions of every pattern, without any of the demo types implementing them.
+///
+/// Note that several patterns use the same name for their abstraction (for instance `Shape` in both the bridge and the
+/// decorator patterns). Only one of them can be re-exported here, the others must be imported from their module.
+pub mod prelude {
+    pub use crate::abstract_factory::{Button, Factory, Factory2, Window};
+    pub use crate::adapter::TargetInterface;
+    pub use crate::bridge::{DrawingAPI, Shape};
+    pub use crate::builder::Builder;
+    pub use crate::chain_of_responsibility::{Handler, PurchasePower};
+    pub use crate::command::Command;
+    pub use crate::composite::Graphic;
+    pub use crate::factory_method::{Car, CarFactory, CarFactory2};
+    pub use crate::mediator::Mediator;
+    pub use crate::observer::{Observable, Observer};
+    pub use crate::proxy::ICar;
+    pub use crate::state::State;
+    pub use crate::strategy::Algorithm;
+}
@@ -1,28 +1,59 @@
-//! A collection of design patterns.
+//! Command line front end to the design patterns.
+//!
+//! Every pattern has a subcommand running its scenario, for instance `patterns chain --amount 12000` or
+//! `patterns switch ON OFF ON`. Run `patterns help` to list them. The documentation of the patterns is available
+//! through `patterns list` and `patterns show <name>`.
 
-#![deny(missing_docs)]
-#![allow(dead_code)]
+use std::env;
+use std::process;
 
-pub mod factory_method;
-pub mod abstract_factory;
-pub mod prototype;
-pub mod singleton;
-pub mod composite;
-pub mod decorator;
-pub mod builder;
-pub mod proxy;
-pub mod command;
-pub mod observer;
-pub mod state;
-pub mod strategy;
-pub mod template_method;
-pub mod adapter;
-pub mod bridge;
-pub mod facade;
-pub mod flyweight;
-pub mod chain_of_responsibility;
-pub mod iterator;
-pub mod mediator;
+mod cli;
+
+use cli::{CliError, SCENARIOS};
+
+/// Returns the help text listing every subcommand.
+fn usage() -> String {
+    let mut text = String::from("usage: patterns <subcommand> [arguments]\n\n");
+    text.push_str("catalogue:\n");
+    text.push_str("  list       [--category <category>] [--json]\n");
+    text.push_str("  show       <pattern> [--json]\n\n");
+    text.push_str("scenarios:\n");
+    for scenario in SCENARIOS {
+        let line = format!("  {:<10} {:<24} {}", scenario.name, scenario.pattern, scenario.usage);
+        text.push_str(line.trim_end());
+        text.push('\n');
+    }
+    text
+}
 
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let name = match args.first() {
+        Some(name)  => name.as_str(),
+        None        => {
+            eprint!("{}", usage());
+            process::exit(2);
+        },
+    };
+    if name == "help" || name == "--help" || name == "-h" {
+        print!("{}", usage());
+        return;
+    }
+
+    let result = match (name, cli::find(name)) {
+        ("list", _)             => cli::list(&args[1..]),
+        ("show", _)             => cli::show(&args[1..]),
+        (_, Some(scenario))     => (scenario.run)(&args[1..]).map(|report| report.to_string()),
+        (_, None)               => Err(CliError::Usage(format!("unknown subcommand: '{}'", name))),
+    };
+    match result {
+        Ok(output)  => print!("{}", output),
+        Err(err)    => {
+            eprintln!("{}", err);
+            if let CliError::Usage(_) = err {
+                eprint!("{}", usage());
+            }
+            process::exit(err.exit_code());
+        },
+    }
 }
@@ -20,30 +20,54 @@
 //! Due to the fact that cyclic references are not allowed in Rust, this makes the mediator class very unelegant and
 //! complex to implement. Hence the sample code below should probably not be used as a template if the behaviour of
 //! the mediator pattern is required.
+//!
+//! # Example
+//! ```
+//! use patterns::mediator::{ButtonBook, ButtonSearch, ButtonView, Display, Mediator, ParticipantMediator};
+//!
+//! let (view, search, book, display) = (ButtonView::new(), ButtonSearch::new(), ButtonBook::new(), Display);
+//! let mut mediator = ParticipantMediator::new();
+//! mediator.register_view(&view);
+//! mediator.register_search(&search);
+//! mediator.register_book(&book);
+//! mediator.register_display(&display);
+//!
+//! assert_eq!(mediator.search(), "searching");
+//! assert_eq!(mediator.get_counts(), (0, 1, 0));
+//! ```
 
 use std::cell::Cell;
 
 /// The mediator interface implemented by a concrete mediator
-trait Mediator<'a> {
+pub trait Mediator<'a> {
+    /// Presses the book button and updates the display.
     fn book(&self) -> &str;
+    /// Presses the view button and updates the display.
     fn view(&self) -> &str;
+    /// Presses the search button and updates the display.
     fn search(&self) -> &str;
+    /// Registers the view button colleague.
     fn register_view(&mut self, view: &'a ButtonView);
+    /// Registers the search button colleague.
     fn register_search(&mut self, search: &'a ButtonSearch);
+    /// Registers the book button colleague.
     fn register_book(&mut self, book: &'a ButtonBook);
+    /// Registers the display colleague.
     fn register_display(&mut self, display: &'a Display);
+    /// Returns how often the view, search and book buttons were pressed.
     fn get_counts(&self) -> (u8, u8, u8);
 }
 
 /// A concrete mediator playing the middle agent between the book, search and view buttons and the display.
-struct ParticipantMediator<'a> {
+pub struct ParticipantMediator<'a> {
     view: Option<&'a ButtonView>,
     search: Option<&'a ButtonSearch>,
     book: Option<&'a ButtonBook>,
     display: Option<&'a Display>,
 }
 impl<'a> ParticipantMediator<'a> {
-    fn new() -> ParticipantMediator<'a> {
+    /// Creates a mediator without any colleagues.
+    pub fn new() -> ParticipantMediator<'a> {
         ParticipantMediator {
             view: None,
             search: None,
@@ -52,6 +76,11 @@ impl<'a> ParticipantMediator<'a> {
         }
     }
 }
+impl<'a> Default for ParticipantMediator<'a> {
+    fn default() -> Self {
+        ParticipantMediator::new()
+    }
+}
 impl<'a> Mediator<'a> for ParticipantMediator<'a> {
     fn register_book(&mut self, book: &'a ButtonBook) {
         self.book = Some(book);
@@ -86,16 +115,22 @@ impl<'a> Mediator<'a> for ParticipantMediator<'a> {
 }
 
 
-trait Button {
+/// The interface shared by all button colleagues.
+pub trait Button {
+    /// Presses the button.
     fn press(&self);
+    /// Returns how often the button was pressed.
     fn get_press_count(&self) -> u8;
 }
 
-struct ButtonBook {
+/// The book button colleague.
+#[derive(Default)]
+pub struct ButtonBook {
     count: Cell<u8>,
 }
 impl ButtonBook {
-    fn new() -> ButtonBook {
+    /// Creates a button that was never pressed.
+    pub fn new() -> ButtonBook {
         ButtonBook {
             count: Cell::new(0),
         }
@@ -111,11 +146,14 @@ impl Button for ButtonBook {
     }
 }
 
-struct ButtonView {
+/// The view button colleague.
+#[derive(Default)]
+pub struct ButtonView {
     count: Cell<u8>,
 }
 impl ButtonView {
-    fn new() -> ButtonView {
+    /// Creates a button that was never pressed.
+    pub fn new() -> ButtonView {
         ButtonView {
             count: Cell::new(0),
         }
@@ -131,11 +169,14 @@ impl Button for ButtonView {
     }
 }
 
-struct ButtonSearch {
+/// The search button colleague.
+#[derive(Default)]
+pub struct ButtonSearch {
     count: Cell<u8>,
 }
 impl ButtonSearch {
-    fn new() -> ButtonSearch {
+    /// Creates a button that was never pressed.
+    pub fn new() -> ButtonSearch {
         ButtonSearch {
             count: Cell::new(0),
         }
@@ -151,9 +192,11 @@ impl Button for ButtonSearch {
     }
 }
 
-struct Display;
+/// The display colleague.
+pub struct Display;
 impl<'a> Display {
-    fn print(&self, string: &'a str) -> &'a str {
+    /// Prints a message on the display.
+    pub fn print(&self, string: &'a str) -> &'a str {
         string
     }
 }
@@ -0,0 +1,289 @@
+//! Fault isolation and priorities.
+//!
+//! With a `Subject`, an observer which panics interrupts the notification, and the observers after it are never
+//! updated. A `GuardedSubject` isolates its observers from each other: observers may report errors, and panics are
+//! caught, such that delivery always continues with the next observer. An observer which panicked may have been left
+//! in an inconsistent state, hence it is skipped by later notifications.
+//!
+//! Every subscription has a priority: observers with a higher priority are updated first, and observers with equal
+//! priorities in the order in which they subscribed. Notifying returns a `DeliveryReport` telling what happened to
+//! every observer.
+
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::rc::Rc;
+use std::result;
+
+use super::{Observable, Observer, Slots, Subscription};
+
+/// An observer whose update can fail.
+pub trait FallibleObserver<E> {
+    /// Updates the observer with an event, or tells why it could not.
+    fn update(&mut self, event: &E) -> result::Result<(), String>;
+}
+
+impl<E, F> FallibleObserver<E> for F where F: FnMut(&E) -> result::Result<(), String> {
+    fn update(&mut self, event: &E) -> result::Result<(), String> {
+        self(event)
+    }
+}
+
+/// What happened to an observer during a notification.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Status {
+    /// The observer was updated.
+    Delivered,
+    /// The observer returned an error.
+    Failed(String),
+    /// The observer panicked, with the given message.
+    Panicked(String),
+    /// The observer was not updated, for the given reason.
+    Skipped(&'static str),
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Status::Delivered               => write!(f, "delivered"),
+            Status::Failed(ref error)       => write!(f, "failed: {}", error),
+            Status::Panicked(ref message)   => write!(f, "panicked: {}", message),
+            Status::Skipped(reason)         => write!(f, "skipped: {}", reason),
+        }
+    }
+}
+
+/// The delivery of an event to an observer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Delivery {
+    /// The name of the observer.
+    pub observer: String,
+    /// The priority of the observer.
+    pub priority: i32,
+    /// What happened to the observer.
+    pub status: Status,
+}
+
+/// The account of a notification, in the order in which observers were considered.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DeliveryReport {
+    /// The delivery to every observer.
+    pub deliveries: Vec<Delivery>,
+}
+
+impl DeliveryReport {
+    /// The names of the observers which were updated.
+    pub fn succeeded(&self) -> Vec<&str> {
+        self.names(|status| *status == Status::Delivered)
+    }
+
+    /// The names of the observers which returned an error or panicked.
+    pub fn failed(&self) -> Vec<&str> {
+        self.names(|status| matches!(*status, Status::Failed(_) | Status::Panicked(_)))
+    }
+
+    /// The names of the observers which were skipped.
+    pub fn skipped(&self) -> Vec<&str> {
+        self.names(|status| matches!(*status, Status::Skipped(_)))
+    }
+
+    /// Whether every observer was updated.
+    pub fn is_success(&self) -> bool {
+        self.deliveries.iter().all(|delivery| delivery.status == Status::Delivered)
+    }
+
+    fn names<P>(&self, predicate: P) -> Vec<&str> where P: Fn(&Status) -> bool {
+        self.deliveries.iter()
+            .filter(|delivery| predicate(&delivery.status))
+            .map(|delivery| delivery.observer.as_str())
+            .collect()
+    }
+}
+
+/// Renders one line per observer, as `logger (priority 10): delivered`.
+impl fmt::Display for DeliveryReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for delivery in &self.deliveries {
+            writeln!(f, "{} (priority {}): {}", delivery.observer, delivery.priority, delivery.status)?;
+        }
+        Ok(())
+    }
+}
+
+/// The message of a caught panic.
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    match (payload.downcast_ref::<&str>(), payload.downcast_ref::<String>()) {
+        (Some(message), _)  => message.to_string(),
+        (_, Some(message))  => message.clone(),
+        _                   => String::from("unknown panic"),
+    }
+}
+
+/// An observer of a guarded subject.
+struct Guarded<E> {
+    name: String,
+    priority: i32,
+    poisoned: Cell<bool>,
+    observer: RefCell<Box<dyn FallibleObserver<E>>>,
+}
+
+/// A subject isolating its observers from each other's failures, and updating them by priority.
+pub struct GuardedSubject<E> {
+    slots: Rc<RefCell<Slots<Guarded<E>>>>,
+}
+
+impl<E> GuardedSubject<E> where E: 'static {
+    /// Creates a subject without observers.
+    pub fn new() -> GuardedSubject<E> {
+        GuardedSubject {
+            slots: Rc::new(RefCell::new(Slots::new())),
+        }
+    }
+
+    /// The number of registered observers.
+    pub fn len(&self) -> usize {
+        self.slots.borrow().len()
+    }
+
+    /// Whether no observer is registered.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Subscribes a named observer with a priority. Observers with higher priorities are updated first.
+    pub fn subscribe_named<O>(&self, name: &str, priority: i32, observer: O) -> Subscription
+        where O: FallibleObserver<E> + 'static {
+        self.add(name, priority, Box::new(observer))
+    }
+
+    /// Notifies all observers by priority, catching their errors and panics.
+    ///
+    /// Observers are skipped if they panicked during an earlier notification, if an earlier observer unsubscribed
+    /// them, or if they are the ones notifying the subject (nested notifications).
+    pub fn notify(&self, event: &E) -> DeliveryReport {
+        let slots = self.slots.borrow().snapshot();
+        let deliveries = slots.iter().map(|slot| {
+            let status = match slot.observer.try_borrow_mut() {
+                _ if !slot.is_active()      => Status::Skipped("unsubscribed"),
+                _ if slot.poisoned.get()    => Status::Skipped("panicked before"),
+                Err(_)                      => Status::Skipped("busy"),
+                Ok(mut observer)            => {
+                    match panic::catch_unwind(AssertUnwindSafe(|| observer.update(event))) {
+                        Ok(Ok(()))      => Status::Delivered,
+                        Ok(Err(error))  => Status::Failed(error),
+                        Err(payload)    => {
+                            slot.poisoned.set(true);
+                            Status::Panicked(panic_message(payload.as_ref()))
+                        },
+                    }
+                },
+            };
+            Delivery { observer: slot.name.clone(), priority: slot.priority, status }
+        }).collect();
+        DeliveryReport { deliveries }
+    }
+
+    fn add(&self, name: &str, priority: i32, observer: Box<dyn FallibleObserver<E>>) -> Subscription {
+        let guarded = Guarded {
+            name: name.to_string(),
+            priority,
+            poisoned: Cell::new(false),
+            observer: RefCell::new(observer),
+        };
+        let id = {
+            let mut slots = self.slots.borrow_mut();
+            // Observers are kept sorted by decreasing priority, after the observers with the same priority.
+            let idx = slots.iter().position(|other| other.priority < priority).unwrap_or(slots.len());
+            slots.insert(idx, guarded)
+        };
+        Subscription::cancelling(&self.slots, move |slots| slots.remove(id))
+    }
+}
+
+impl<E> Default for GuardedSubject<E> where E: 'static {
+    fn default() -> Self {
+        GuardedSubject::new()
+    }
+}
+
+/// Registering an infallible observer subscribes it with priority 0, under the name `observer`.
+impl<E> Observable<E> for GuardedSubject<E> where E: 'static {
+    fn register(&self, mut observer: Box<dyn Observer<E>>) -> Subscription {
+        self.add("observer", 0, Box::new(move |event: &E| {
+            observer.update(event);
+            Ok(())
+        }))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guarded_subject() {
+        let subject = GuardedSubject::new();
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let log = Rc::clone(&received);
+        let _audit = subject.subscribe_named("audit", 0, move |event: &i32| {
+            log.borrow_mut().push(*event);
+            Ok(())
+        });
+        let _validator = subject.subscribe_named("validator", 10, |event: &i32| match *event {
+            event if event < 0  => Err(format!("{} is negative", event)),
+            _                   => Ok(()),
+        });
+        let _parser = subject.subscribe_named("parser", 5, |event: &i32| {
+            if *event == 0 {
+                panic!("cannot parse zero");
+            }
+            Ok(())
+        });
+        let _logger = subject.subscribe_named("logger", 0, |_: &i32| Ok(()));
+
+        let report = subject.notify(&1);
+        assert!(report.is_success());
+        assert_eq!(report.succeeded(), vec!["validator", "parser", "audit", "logger"]);
+
+        let report = subject.notify(&-1);
+        assert_eq!(report.failed(), vec!["validator"]);
+        assert_eq!(report.deliveries[0].status, Status::Failed("-1 is negative".to_string()));
+
+        // The panic is caught, the observers after the parser are still updated.
+        let report = subject.notify(&0);
+        assert_eq!(report.to_string(), "validator (priority 10): delivered\nparser (priority 5): panicked: cannot \
+                                        parse zero\naudit (priority 0): delivered\nlogger (priority 0): delivered\n");
+        assert_eq!(subject.notify(&2).skipped(), vec!["parser"]);
+        assert_eq!(*received.borrow(), vec![1, -1, 0, 2]);
+    }
+
+    #[test]
+    fn test_guarded_subject_skips() {
+        let subject: Rc<GuardedSubject<&str>> = Rc::new(GuardedSubject::new());
+        let handles: Rc<RefCell<Vec<Subscription>>> = Rc::default();
+        let slots = Rc::clone(&handles);
+        subject.subscribe_named("first", 1, move |_: &&str| {
+            slots.borrow_mut().clear();
+            Ok(())
+        }).forget();
+        handles.borrow_mut().push(subject.subscribe_named("second", 0, |_: &&str| Ok(())));
+        let source = Rc::downgrade(&subject);
+        subject.subscribe_named("echo", -1, move |event: &&str| match source.upgrade() {
+            Some(subject) if *event == "ping"   => {
+                let report = subject.notify(&"pong");
+                assert_eq!(report.skipped(), vec!["echo"]);
+                Ok(())
+            },
+            _                                   => Ok(()),
+        }).forget();
+        subject.subscribe(|_: &&str| ()).forget();
+
+        let report = subject.notify(&"ping");
+        let statuses: Vec<String> = report.deliveries.iter().map(|delivery| delivery.status.to_string()).collect();
+        assert_eq!(statuses, vec!["delivered", "skipped: unsubscribed", "delivered", "delivered"]);
+        assert_eq!(report.succeeded(), vec!["first", "observer", "echo"]);
+        assert_eq!(subject.len(), 3);
+    }
+}
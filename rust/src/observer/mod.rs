@@ -0,0 +1,561 @@
+//! Oberver design pattern.
+//!
+//! # Theory
+//! Defines a one-to-many dependency between objects so that when one object (the subject) changes the state, all its
+//! dependents (observers) are notified and updated automatically. This ensures consistency between related objects.
+//! Moreover, it avoids tight coupling, as one object does not need to know about the other, hence objects remain
+//! flexible and reusable. The separation of the underlying application data and representation is usually the goal (for
+//! example in the Model-View-Controller model). It follows a kind of publish-subscribe principle which is used when
+//! objects depend on each other and a change to one of the objects requires changing others.
+//!
+//! # Participants
+//! - `Observable`: the subject interface. It provides an interface for adding observers, and notifies them when its
+//!   state changes. Such a subject may have any number of dependent observers.
+//! - `Observer`: defines the `update` method from the interface to publish notifications. The observer gets notified
+//!   about any change in data through this method. Closures taking a reference to the event are observers too.
+//! - `Subject`: a reusable implementation of `Observable` for any event type. It owns its observers, and returns a
+//!   `Subscription` for each of them, which unsubscribes the observer when dropped.
+//! - `Model`: the concrete subject being observed. It has state that is of interest to the observers.
+//! - `View`: the concrete observers. They are dependent on `Model`, hence requiring the observation. They should
+//!   implement the `Observer` interface to remain consistent with the current state of `Model` (the subject).
+//!
+//! # Modifications and Strategies
+//! - Observers can postpone its updates until it gets a notification from the subject.
+//! - One can change the implemention such that observers might not have associated subjects. If the source of the event
+//!   is known, the observer can then choose to ignore it.
+//! - One can implement a many-to-many relationship between observers and subjects. If this is the case, the interface
+//!   might need modification if the observer requires knowledge of which object sent it a notification.
+//! - _Change managers_: such an implementation waits before triggering the notification (different update strategy).
+//!   Then several changes can be fused. This is faster, but more error-prone. `Model::batch` fuses the changes made
+//!   in a transaction into a single notification.
+//! - Observers might want to register for specific event types only. The `topic` module routes messages by hierarchical
+//!   topic, with wildcard patterns and filters on the payload.
+//! - Observers can be unsubscribed. Here, subscribing returns a handle which unsubscribes the observer when it is
+//!   dropped, hence the lifetime of the subscription is that of the handle rather than that of the subject. This is
+//!   safe even while observers are being notified: an observer unsubscribed by an earlier one is not updated anymore.
+//! - _Push model_: the update interface provides the update information. This is what is implemented below. The reason
+//!   for this is that in Rust, a _pull request_ would require the Observer to (at least "temporarily") hold a reference
+//!   to the subject. This would prevent the subject from being mutable. Moreover, this would not be thread safe if the
+//!   subject and the observers live in different threads (e.g. the observer is a GUI in its own thread).
+//!   The `threaded` module runs every observer on a thread of its own, and sends it the events through a channel.
+//! - Observers often transform the events before acting on them. The `reactive` module composes such transformations
+//!   as operators on streams of events.
+//! - A mediator can be used if there are many observers and subjects. It then acts as an observer for subjects and as a
+//!   subject for observers.
+//!
+//! # Attention
+//! Note that a simple operation can easily cause many unexpected updates via a cascade effect. This can be solved by
+//! providing only updates during idle times. Another solution is to notify observers only if a change state actually
+//! occurs in the state object, which `Model` does. Otherwise, observers might only want to register for certain events,
+//! which reduces the overall number of update calls.
+//!
+//! On top of that, references to deleted subjects must be updated (i.e. deleted if they only depend on the deleted
+//! subject). Note that this can be solved by the subject owning is observers in a one-to-many model. However, this
+//! can obviously not be done in many-to-many models.
+//! Conversely, a subject referencing observers should not keep them alive after the rest of the application is done
+//! with them. The `weak` module holds observers through weak references, and forgets them once they are dropped.
+//!
+//! Observers should not depend on each other: one failing observer should not prevent the others from being notified,
+//! and the order of notifications should not be an accident of registration. The `guarded` module catches errors and
+//! panics per observer, and updates observers by priority.
+//!
+//! Note that cyclic references here can be a problem as this results in an infinite chain of udpates. This is not a
+//! problem with Rust if writing safe code as such cyclic references are not allowed.
+//!
+//! # Known Uses
+//! - ColorPicker
+//! - JDRAW: draw model
+//! - AWT event handling in Java
+//!
+//! # Note
+//! Note that the way this is implemented below allows for any type of listener (i.e. as long as it implements the
+//! `Observer` interface). This might not be desired in some situations where one wants to restrict the listeners to
+//! have a single type. In such a case, use generics to make every observable only observable by a single observer type.
+//!
+//! Moreover, note that `Subject` uses interior mutability: subscribing and notifying only require a shared reference,
+//! such that subscriptions can be cancelled from anywhere, including from within an observer being notified.
+//!
+//! # Example
+//! ```
+//! use patterns::observer::{Model, Observable, View};
+//!
+//! let view = View::new("main");
+//! let mut model = Model::new();
+//! let subscription = model.subscribe(view.clone());
+//! model.set_data(42);
+//! model.set_data(42);
+//! assert_eq!(view.lines(), vec!["View main got data: 42 (was 0)"]);
+//!
+//! model.batch(|model| {
+//!     model.set_data(7);
+//!     model.set_data(43);
+//! });
+//! assert_eq!(view.lines()[1], "View main got data: 43 (was 42)");
+//!
+//! drop(subscription);
+//! model.set_data(44);
+//! assert_eq!(view.lines().len(), 2);
+//! ```
+
+pub mod guarded;
+pub mod reactive;
+pub mod threaded;
+pub mod topic;
+pub mod weak;
+
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::ops::Deref;
+use std::panic::{self, AssertUnwindSafe};
+use std::rc::Rc;
+
+/// The trait implemented by observers
+pub trait Observer<E> {
+    /// The function called by the observed object with the event that occurred.
+    fn update(&mut self, event: &E);
+}
+
+impl<E, F> Observer<E> for F where F: FnMut(&E) {
+    fn update(&mut self, event: &E) {
+        self(event)
+    }
+}
+
+/// The trait implemented by an observable object.
+pub trait Observable<E> {
+    /// Registers a new observer for this object. It stays registered until the subscription is dropped.
+    fn register(&self, observer: Box<dyn Observer<E>>) -> Subscription;
+
+    /// Registers a new observer for this object. It stays registered until the subscription is dropped.
+    fn subscribe<O>(&self, observer: O) -> Subscription where O: Observer<E> + 'static, Self: Sized {
+        self.register(Box::new(observer))
+    }
+}
+
+/// A handle keeping an observer registered. Dropping it unsubscribes the observer.
+#[must_use = "the observer is unsubscribed as soon as the subscription is dropped"]
+pub struct Subscription {
+    cancel: Option<Box<dyn FnOnce()>>,
+}
+
+impl Subscription {
+    /// Creates a subscription calling `cancel` when it is dropped.
+    pub fn new<F>(cancel: F) -> Subscription where F: FnOnce() + 'static {
+        Subscription {
+            cancel: Some(Box::new(cancel)),
+        }
+    }
+
+    /// Unsubscribes the observer now.
+    pub fn unsubscribe(self) {}
+
+    /// Keeps the observer registered for as long as the subject lives.
+    pub fn forget(mut self) {
+        self.cancel = None;
+    }
+
+    /// Creates a subscription calling `cancel` with the state of a subject when it is dropped, unless the subject is
+    /// gone by then. What `cancel` returns, such as the observer it removed, is only dropped once the state is
+    /// released, as that observer may own subscriptions to the same subject.
+    fn cancelling<S, F, R>(state: &Rc<RefCell<S>>, cancel: F) -> Subscription
+        where S: 'static, F: FnOnce(&mut S) -> R + 'static {
+        let state = Rc::downgrade(state);
+        Subscription::new(move || {
+            if let Some(state) = state.upgrade() {
+                let removed = cancel(&mut state.borrow_mut());
+                drop(removed);
+            }
+        })
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        if let Some(cancel) = self.cancel.take() {
+            cancel();
+        }
+    }
+}
+
+impl fmt::Debug for Subscription {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Subscription").field("active", &self.cancel.is_some()).finish()
+    }
+}
+
+/// An entry of a subject, such as an observer, along with the identifier of its subscription. Notifications in
+/// progress share it, and skip it once it is removed.
+struct Slot<T> {
+    id: usize,
+    active: Cell<bool>,
+    item: T,
+}
+
+impl<T> Slot<T> {
+    /// Creates an entry which is part of its subject.
+    fn new(id: usize, item: T) -> Rc<Slot<T>> {
+        Rc::new(Slot { id, active: Cell::new(true), item })
+    }
+
+    /// Whether the entry is still part of the subject.
+    fn is_active(&self) -> bool {
+        self.active.get()
+    }
+
+    /// Marks the entry as removed from the subject.
+    fn deactivate(&self) {
+        self.active.set(false);
+    }
+}
+
+impl<T> Deref for Slot<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.item
+    }
+}
+
+/// The entries of a subject, in the order in which they are notified. Notifications iterate over a snapshot of the
+/// entries, such that observers can subscribe and unsubscribe others while being notified.
+struct Slots<T> {
+    next: usize,
+    slots: Vec<Rc<Slot<T>>>,
+}
+
+impl<T> Slots<T> {
+    fn new() -> Slots<T> {
+        Slots {
+            next: 0,
+            slots: Vec::new(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &Rc<Slot<T>>> {
+        self.slots.iter()
+    }
+
+    /// Adds an entry at the end, and returns its identifier.
+    fn push(&mut self, item: T) -> usize {
+        self.insert(self.slots.len(), item)
+    }
+
+    /// Adds an entry at the given position, and returns its identifier.
+    fn insert(&mut self, idx: usize, item: T) -> usize {
+        let id = self.next;
+        self.next += 1;
+        self.slots.insert(idx, Slot::new(id, item));
+        id
+    }
+
+    /// Removes an entry, and returns it if it was part of the subject.
+    fn remove(&mut self, id: usize) -> Option<Rc<Slot<T>>> {
+        let slot = self.slots.remove(self.slots.iter().position(|slot| slot.id == id)?);
+        slot.deactivate();
+        Some(slot)
+    }
+
+    /// Only keeps the entries satisfying the predicate. Returns how many were removed.
+    fn retain<P>(&mut self, mut keep: P) -> usize where P: FnMut(&Slot<T>) -> bool {
+        let len = self.slots.len();
+        self.slots.retain(|slot| {
+            let kept = keep(slot);
+            if !kept {
+                slot.deactivate();
+            }
+            kept
+        });
+        len - self.slots.len()
+    }
+
+    /// The entries as they are now, for a notification.
+    fn snapshot(&self) -> Vec<Rc<Slot<T>>> {
+        self.slots.clone()
+    }
+}
+
+/// An observer registered with a subject.
+type Registered<E> = RefCell<Box<dyn Observer<E>>>;
+
+/// A subject notifying observers of events of type `E`.
+pub struct Subject<E> {
+    slots: Rc<RefCell<Slots<Registered<E>>>>,
+}
+
+impl<E> Subject<E> where E: 'static {
+    /// Creates a subject without observers.
+    pub fn new() -> Subject<E> {
+        Subject {
+            slots: Rc::new(RefCell::new(Slots::new())),
+        }
+    }
+
+    /// The number of registered observers.
+    pub fn len(&self) -> usize {
+        self.slots.borrow().len()
+    }
+
+    /// Whether no observer is registered.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Notifies all observers, in the order in which they subscribed, and returns how many were updated.
+    ///
+    /// Observers may subscribe or unsubscribe others while being notified: new observers only receive the next
+    /// events, and unsubscribed ones are not updated anymore. An observer which notifies the subject it observes is
+    /// not updated again with the nested event.
+    pub fn notify(&self, event: &E) -> usize {
+        let slots = self.slots.borrow().snapshot();
+        let mut updated = 0;
+        for slot in slots.iter().filter(|slot| slot.is_active()) {
+            if let Ok(mut observer) = slot.try_borrow_mut() {
+                observer.update(event);
+                updated += 1;
+            }
+        }
+        updated
+    }
+}
+
+impl<E> Observable<E> for Subject<E> where E: 'static {
+    fn register(&self, observer: Box<dyn Observer<E>>) -> Subscription {
+        let id = self.slots.borrow_mut().push(RefCell::new(observer));
+        Subscription::cancelling(&self.slots, move |slots| slots.remove(id))
+    }
+}
+
+impl<E> Default for Subject<E> where E: 'static {
+    fn default() -> Self {
+        Subject::new()
+    }
+}
+
+/// The observer. Clones of a view share the lines it displays.
+#[derive(Clone, Debug)]
+pub struct View {
+    name: String,
+    lines: Rc<RefCell<Vec<String>>>,
+}
+
+impl View {
+    /// Creates a view with a given name.
+    pub fn new(name: &str) -> View {
+        View {
+            name: name.to_string(),
+            lines: Rc::new(RefCell::new(Vec::new())),
+        }
+    }
+
+    /// The lines displayed by the view, one per update.
+    pub fn lines(&self) -> Vec<String> {
+        self.lines.borrow().clone()
+    }
+}
+
+impl Observer<u64> for View {
+    fn update(&mut self, data: &u64) {
+        self.lines.borrow_mut().push(format!("View {} got data: {}", self.name, data));
+    }
+}
+
+impl Observer<Change<u64>> for View {
+    fn update(&mut self, change: &Change<u64>) {
+        self.lines.borrow_mut().push(format!("View {} got data: {} (was {})", self.name, change.new, change.old));
+    }
+}
+
+/// The notification of a change of value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Change<T> {
+    /// The value before the change.
+    pub old: T,
+    /// The value after the change.
+    pub new: T,
+}
+
+/// The subject. Observers are only notified when the data actually changes.
+pub struct Model {
+    data: u64,
+    subject: Subject<Change<u64>>,
+    batch: Option<u64>,
+}
+
+impl Model {
+    /// Creates a model without any observers.
+    pub fn new() -> Model {
+        Model {
+            data: 0,
+            subject: Subject::new(),
+            batch: None,
+        }
+    }
+
+    /// Returns the current state of the model.
+    pub fn get_data(&self) -> u64 {
+        self.data
+    }
+
+    /// Updates the state of the model and notifies all observers, unless the data did not change or a batch is in
+    /// progress. Returns how many observers were updated.
+    pub fn set_data(&mut self, data: u64) -> usize {
+        let old = self.data;
+        self.data = data;
+        match self.batch {
+            None if old != data => self.subject.notify(&Change { old, new: data }),
+            _                   => 0,
+        }
+    }
+
+    /// Runs a transaction: the updates it makes are combined into a single notification once it is done, carrying the
+    /// data from before the transaction. No notification is sent if the data ends up unchanged. Nested batches are
+    /// part of the outermost one. If the transaction panics, the batch is rolled back: the model gets the data from
+    /// before the transaction again and the panic carries on without notifying anyone, as observers never saw the
+    /// partial updates.
+    pub fn batch<F, R>(&mut self, transaction: F) -> R where F: FnOnce(&mut Model) -> R {
+        if self.batch.is_some() {
+            return transaction(self);
+        }
+        self.batch = Some(self.data);
+        let result = panic::catch_unwind(AssertUnwindSafe(|| transaction(self)));
+        let old = self.batch.take().unwrap();
+        match result {
+            Ok(result)      => {
+                if old != self.data {
+                    self.subject.notify(&Change { old, new: self.data });
+                }
+                result
+            },
+            Err(payload)    => {
+                self.data = old;
+                panic::resume_unwind(payload)
+            },
+        }
+    }
+}
+
+impl Default for Model {
+    fn default() -> Self {
+        Model::new()
+    }
+}
+
+impl Observable<Change<u64>> for Model {
+    fn register(&self, observer: Box<dyn Observer<Change<u64>>>) -> Subscription {
+        self.subject.register(observer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_observer() {
+        let views: Vec<View> = (0..3).map(|idx| View::new(&format!("view_{}", idx))).collect();
+        let mut subject = Model::new();
+        let subscriptions: Vec<Subscription> = views.iter().map(|view| subject.subscribe(view.clone())).collect();
+
+        assert_eq!(subject.set_data(24), 3);
+        subject.set_data(100);
+        assert_eq!(views[0].lines(), vec!["View view_0 got data: 24 (was 0)", "View view_0 got data: 100 (was 24)"]);
+        assert_eq!(views[2].lines()[1], "View view_2 got data: 100 (was 24)");
+
+        let mut subscriptions = subscriptions.into_iter();
+        subscriptions.next().unwrap().unsubscribe();
+        subscriptions.next().unwrap().forget();
+        assert_eq!(subject.set_data(1130113), 2);
+        assert_eq!(views[0].lines().len(), 2);
+        assert_eq!(views[1].lines()[2], "View view_1 got data: 1130113 (was 100)");
+        drop(subscriptions);
+        assert_eq!(subject.set_data(7), 1);
+    }
+
+    #[test]
+    fn test_change_notifications() {
+        let mut model = Model::new();
+        let changes = Rc::new(RefCell::new(Vec::new()));
+        let log = Rc::clone(&changes);
+        let _subscription = model.subscribe(move |change: &Change<u64>| log.borrow_mut().push(*change));
+
+        assert_eq!(model.set_data(0), 0);
+        assert_eq!(model.set_data(5), 1);
+        assert_eq!(model.set_data(5), 0);
+        let doubled = model.batch(|model| {
+            assert_eq!(model.set_data(6), 0);
+            model.batch(|model| model.set_data(model.get_data() * 2));
+            model.get_data()
+        });
+        assert_eq!(doubled, 12);
+        // A batch leaving the data unchanged does not notify.
+        model.batch(|model| {
+            model.set_data(1);
+            model.set_data(12);
+        });
+        assert_eq!(*changes.borrow(), vec![Change { old: 0, new: 5 }, Change { old: 5, new: 12 }]);
+
+        // A panicking transaction ends the batch and rolls its updates back without notifying.
+        let result = panic::catch_unwind(AssertUnwindSafe(|| model.batch(|model| {
+            model.set_data(13);
+            model.batch(|model| model.set_data(14));
+            panic!("transaction failed");
+        })));
+        assert!(result.is_err());
+        assert_eq!(model.get_data(), 12);
+        assert_eq!(changes.borrow().len(), 2);
+        assert_eq!(model.set_data(15), 1);
+        assert_eq!(changes.borrow().last(), Some(&Change { old: 12, new: 15 }));
+    }
+
+    #[test]
+    fn test_unsubscribe_during_notification() {
+        let subject: Rc<Subject<String>> = Rc::new(Subject::new());
+        let received = Rc::new(RefCell::new(Vec::new()));
+
+        // The first observer unsubscribes itself and the third one on the first event.
+        let handles: Rc<RefCell<Vec<Subscription>>> = Rc::default();
+        let (log, slots) = (Rc::clone(&received), Rc::clone(&handles));
+        handles.borrow_mut().push(subject.subscribe(move |event: &String| {
+            log.borrow_mut().push(format!("first: {}", event));
+            slots.borrow_mut().clear();
+        }));
+        let log = Rc::clone(&received);
+        let second = subject.subscribe(move |event: &String| log.borrow_mut().push(format!("second: {}", event)));
+        let log = Rc::clone(&received);
+        handles.borrow_mut().push(subject.subscribe(move |event: &String| {
+            log.borrow_mut().push(format!("third: {}", event))
+        }));
+
+        // An observer which notifies its own subject is not updated again.
+        let (log, source) = (Rc::clone(&received), Rc::downgrade(&subject));
+        subject.subscribe(move |event: &String| {
+            log.borrow_mut().push(format!("fourth: {}", event));
+            if let Some(subject) = source.upgrade() {
+                subject.notify(&format!("echo of {}", event));
+            }
+        }).forget();
+
+        assert_eq!(subject.len(), 4);
+        assert_eq!(subject.notify(&"a".to_string()), 3);
+        assert_eq!(*received.borrow(), vec!["first: a", "second: a", "fourth: a", "second: echo of a"]);
+        assert_eq!(subject.len(), 2);
+        drop(second);
+        received.borrow_mut().clear();
+        subject.notify(&"b".to_string());
+        assert_eq!(*received.borrow(), vec!["fourth: b"]);
+
+        // Dropping an observer which owns a subscription to the same subject cancels that one too.
+        let subject: Subject<String> = Subject::new();
+        let inner = subject.subscribe(|_: &String| ());
+        let outer = subject.subscribe(move |_: &String| {
+            let _ = &inner;
+        });
+        assert_eq!(subject.len(), 2);
+        drop(outer);
+        assert_eq!(subject.len(), 0);
+    }
+}
@@ -0,0 +1,342 @@
+//! Reactive operators on streams of events.
+//!
+//! A `Stream` is an observable sequence of events. Operators derive new streams from existing ones, such that the glue
+//! between a subject and its observers can be composed rather than written by hand:
+//! - `map`, `filter` and `scan` transform, select and accumulate events;
+//! - `distinct_until_changed` skips events equal to the previous one;
+//! - `debounce` only emits an event once no other event followed it for some time, and `throttle` emits at most one
+//!   event per period;
+//! - `merge` interleaves the events of two streams, and `combine_latest` pairs the latest events of two streams.
+//!
+//! Any `Observable`, such as `Model`, can be turned into a stream, and streams are observables themselves. A derived
+//! stream keeps the streams it is derived from alive. Dropping it unsubscribes it from them, hence its observers only
+//! receive events as long as the stream, or one of its clones, lives.
+//!
+//! Time based operators do not read the time of the system: they are driven by a `TimeSource`, which has a `Clock`
+//! and runs deferred tasks. The `VirtualTime` only moves its `ManualClock` when told so, which makes tests
+//! deterministic.
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::Duration;
+
+use super::{Observable, Observer, Subject, Subscription};
+use crate::clock::{Clock, ManualClock};
+
+/// Tells the time and runs deferred tasks, for the time based operators.
+pub trait TimeSource {
+    /// The clock telling the current time.
+    fn clock(&self) -> &dyn Clock;
+    /// Runs a task once the delay has elapsed.
+    fn schedule(&self, delay: Duration, task: Box<dyn FnOnce()>);
+}
+
+/// A deferred task: its due time, its sequence number and what it does.
+type Task = (Duration, usize, Box<dyn FnOnce()>);
+
+/// A time source whose clock only moves when `advance` is called.
+#[derive(Default)]
+pub struct VirtualTime {
+    clock: ManualClock,
+    sequence: Cell<usize>,
+    tasks: RefCell<Vec<Task>>,
+}
+
+impl VirtualTime {
+    /// Creates a time source whose clock shows zero.
+    pub fn new() -> VirtualTime {
+        VirtualTime::default()
+    }
+
+    /// The number of tasks waiting to run.
+    pub fn pending(&self) -> usize {
+        self.tasks.borrow().len()
+    }
+
+    /// Moves the clock forward, running the tasks due in the meantime in order of their due time, and in the order
+    /// they were scheduled for equal due times. Tasks scheduled by these tasks run too if they are due.
+    pub fn advance(&self, by: Duration) {
+        let target = self.clock.now() + by;
+        loop {
+            let task = {
+                let mut tasks = self.tasks.borrow_mut();
+                let next = tasks.iter().enumerate()
+                    .filter(|(_, &(due, _, _))| due <= target)
+                    .min_by_key(|(_, &(due, sequence, _))| (due, sequence))
+                    .map(|(idx, _)| idx);
+                next.map(|idx| tasks.remove(idx))
+            };
+            match task {
+                Some((due, _, task))    => {
+                    self.clock.set(due.max(self.clock.now()));
+                    task();
+                },
+                None                    => break,
+            }
+        }
+        self.clock.set(target);
+    }
+}
+
+impl TimeSource for VirtualTime {
+    fn clock(&self) -> &dyn Clock {
+        &self.clock
+    }
+
+    fn schedule(&self, delay: Duration, task: Box<dyn FnOnce()>) {
+        self.sequence.set(self.sequence.get() + 1);
+        self.tasks.borrow_mut().push((self.clock.now() + delay, self.sequence.get(), task));
+    }
+}
+
+/// The subscriptions keeping a derived stream connected to the streams it is derived from.
+struct Upstream {
+    _subscriptions: Vec<Subscription>,
+    _parents: Vec<Rc<Upstream>>,
+}
+
+/// An observable sequence of events.
+pub struct Stream<E> {
+    subject: Rc<Subject<E>>,
+    upstream: Rc<Upstream>,
+}
+
+impl<E> Stream<E> where E: 'static {
+    /// Creates a stream emitting the events given to `emit`.
+    pub fn new() -> Stream<E> {
+        Stream {
+            subject: Rc::new(Subject::new()),
+            upstream: Rc::new(Upstream { _subscriptions: Vec::new(), _parents: Vec::new() }),
+        }
+    }
+
+    /// Creates a stream of the events of an observable.
+    pub fn from_observable<O>(source: &O) -> Stream<E> where O: Observable<E> {
+        let subject = Rc::new(Subject::new());
+        let target = Rc::clone(&subject);
+        let subscription = source.subscribe(move |event: &E| {
+            target.notify(event);
+        });
+        Stream { subject, upstream: Rc::new(Upstream { _subscriptions: vec![subscription], _parents: Vec::new() }) }
+    }
+
+    /// Emits an event to the observers of the stream, and returns how many were updated.
+    pub fn emit(&self, event: &E) -> usize {
+        self.subject.notify(event)
+    }
+
+    /// Transforms every event.
+    pub fn map<T, F>(&self, f: F) -> Stream<T> where T: 'static, F: Fn(&E) -> T + 'static {
+        self.derive(move |event, target| {
+            target.notify(&f(event));
+        })
+    }
+
+    /// Only keeps the events satisfying a predicate.
+    pub fn filter<P>(&self, predicate: P) -> Stream<E> where P: Fn(&E) -> bool + 'static {
+        self.derive(move |event, target| {
+            if predicate(event) {
+                target.notify(event);
+            }
+        })
+    }
+
+    /// Emits the accumulation of all events so far, starting from a seed.
+    pub fn scan<A, F>(&self, seed: A, f: F) -> Stream<A> where A: 'static, F: Fn(&A, &E) -> A + 'static {
+        let mut accumulator = seed;
+        self.derive(move |event, target| {
+            accumulator = f(&accumulator, event);
+            target.notify(&accumulator);
+        })
+    }
+
+    /// Skips the events equal to the previous one.
+    pub fn distinct_until_changed(&self) -> Stream<E> where E: Clone + PartialEq {
+        let mut last: Option<E> = None;
+        self.derive(move |event, target| {
+            if last.as_ref() != Some(event) {
+                last = Some(event.clone());
+                target.notify(event);
+            }
+        })
+    }
+
+    /// Emits an event once no other event followed it for the given period. Events followed too soon are dropped.
+    pub fn debounce(&self, period: Duration, time: Rc<dyn TimeSource>) -> Stream<E> where E: Clone {
+        let pending: Rc<RefCell<(usize, Option<E>)>> = Rc::new(RefCell::new((0, None)));
+        self.derive(move |event, target| {
+            let generation = {
+                let mut pending = pending.borrow_mut();
+                *pending = (pending.0 + 1, Some(event.clone()));
+                pending.0
+            };
+            let (pending, target) = (Rc::clone(&pending), Rc::downgrade(target));
+            time.schedule(period, Box::new(move || {
+                let mut pending = pending.borrow_mut();
+                let event = if pending.0 == generation { pending.1.take() } else { None };
+                drop(pending);
+                if let (Some(event), Some(target)) = (event, target.upgrade()) {
+                    target.notify(&event);
+                }
+            }));
+        })
+    }
+
+    /// Emits an event, and then drops the events following it within the given period.
+    pub fn throttle(&self, period: Duration, time: Rc<dyn TimeSource>) -> Stream<E> {
+        let mut last: Option<Duration> = None;
+        self.derive(move |event, target| {
+            let now = time.clock().now();
+            if last.is_none_or(|last| now - last >= period) {
+                last = Some(now);
+                target.notify(event);
+            }
+        })
+    }
+
+    /// Emits the events of both streams, as they occur.
+    pub fn merge(&self, other: &Stream<E>) -> Stream<E> {
+        let subject = Rc::new(Subject::new());
+        let subscriptions = [self, other].iter().map(|source| {
+            let target = Rc::clone(&subject);
+            source.subject.subscribe(move |event: &E| {
+                target.notify(event);
+            })
+        }).collect();
+        let parents = vec![Rc::clone(&self.upstream), Rc::clone(&other.upstream)];
+        Stream { subject, upstream: Rc::new(Upstream { _subscriptions: subscriptions, _parents: parents }) }
+    }
+
+    /// Emits the latest events of both streams whenever either emits, once both have emitted.
+    pub fn combine_latest<T>(&self, other: &Stream<T>) -> Stream<(E, T)> where E: Clone, T: Clone + 'static {
+        let subject = Rc::new(Subject::new());
+        let latest: Rc<RefCell<(Option<E>, Option<T>)>> = Rc::new(RefCell::new((None, None)));
+        let emit = |target: &Subject<(E, T)>, latest: &RefCell<(Option<E>, Option<T>)>| {
+            let pair = match *latest.borrow() {
+                (Some(ref left), Some(ref right))   => Some((left.clone(), right.clone())),
+                _                                   => None,
+            };
+            if let Some(pair) = pair {
+                target.notify(&pair);
+            }
+        };
+        let (target, state) = (Rc::clone(&subject), Rc::clone(&latest));
+        let left = self.subject.subscribe(move |event: &E| {
+            state.borrow_mut().0 = Some(event.clone());
+            emit(&target, &state);
+        });
+        let (target, state) = (Rc::clone(&subject), latest);
+        let right = other.subject.subscribe(move |event: &T| {
+            state.borrow_mut().1 = Some(event.clone());
+            emit(&target, &state);
+        });
+        let parents = vec![Rc::clone(&self.upstream), Rc::clone(&other.upstream)];
+        Stream { subject, upstream: Rc::new(Upstream { _subscriptions: vec![left, right], _parents: parents }) }
+    }
+
+    /// Creates a stream fed by a function of the events of this stream and the stream to feed.
+    fn derive<T, F>(&self, mut forward: F) -> Stream<T> where T: 'static, F: FnMut(&E, &Rc<Subject<T>>) + 'static {
+        let subject = Rc::new(Subject::new());
+        let target = Rc::clone(&subject);
+        let subscription = self.subject.subscribe(move |event: &E| forward(event, &target));
+        let upstream = Upstream { _subscriptions: vec![subscription], _parents: vec![Rc::clone(&self.upstream)] };
+        Stream { subject, upstream: Rc::new(upstream) }
+    }
+}
+
+impl<E> Clone for Stream<E> {
+    fn clone(&self) -> Self {
+        Stream {
+            subject: Rc::clone(&self.subject),
+            upstream: Rc::clone(&self.upstream),
+        }
+    }
+}
+
+impl<E> Default for Stream<E> where E: 'static {
+    fn default() -> Self {
+        Stream::new()
+    }
+}
+
+impl<E> Observable<E> for Stream<E> where E: 'static {
+    fn register(&self, observer: Box<dyn Observer<E>>) -> Subscription {
+        self.subject.register(observer)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{Change, Model};
+
+    fn collect<E>(stream: &Stream<E>) -> (Rc<RefCell<Vec<E>>>, Subscription) where E: Clone + 'static {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let target = Rc::clone(&events);
+        let subscription = stream.subscribe(move |event: &E| target.borrow_mut().push(event.clone()));
+        (events, subscription)
+    }
+
+    #[test]
+    fn test_operators() {
+        let mut model = Model::new();
+        let values = Stream::from_observable(&model).map(|change: &Change<u64>| change.new);
+        let labels = values.filter(|&value| value % 2 == 0)
+            .map(|&value| value / 10)
+            .distinct_until_changed()
+            .map(|decade| format!("{}0s", decade));
+        let totals = values.scan(0, |total, value| total + value);
+        let (labels_seen, _labels) = collect(&labels);
+        let (totals_seen, _totals) = collect(&totals);
+        drop(values);
+
+        for value in [12, 14, 15, 16, 20, 22, 31, 46] {
+            model.set_data(value);
+        }
+        assert_eq!(*labels_seen.borrow(), vec!["10s", "20s", "40s"]);
+        assert_eq!(totals_seen.borrow().last(), Some(&176));
+
+        // Dropping the last stream of a chain unsubscribes it from the model.
+        drop(labels);
+        assert_eq!(model.set_data(48), 1);
+        drop(totals);
+        assert_eq!(model.set_data(50), 0);
+        assert_eq!(labels_seen.borrow().len(), 3);
+    }
+
+    #[test]
+    fn test_time_operators() {
+        let time = Rc::new(VirtualTime::new());
+        let keys: Stream<char> = Stream::new();
+        let debounce = keys.debounce(Duration::from_millis(300), time.clone());
+        let throttle = keys.throttle(Duration::from_millis(300), time.clone());
+        let (debounced, _debounced) = collect(&debounce);
+        let (throttled, _throttled) = collect(&throttle);
+        let type_key = |key: char, after: u64| {
+            time.advance(Duration::from_millis(after));
+            keys.emit(&key);
+        };
+        type_key('a', 0);
+        type_key('b', 100);
+        type_key('c', 250);
+        type_key('d', 400);
+        type_key('e', 100);
+        time.advance(Duration::from_secs(1));
+        assert_eq!(*debounced.borrow(), vec!['c', 'e']);
+        assert_eq!(*throttled.borrow(), vec!['a', 'c', 'd']);
+        assert_eq!(time.pending(), 0);
+
+        let (numbers, letters) = (Stream::new(), Stream::new());
+        let (merge, combine) = (numbers.map(|number: &u32| number.to_string()).merge(&letters),
+                                numbers.combine_latest(&letters));
+        let (merged, _merged) = collect(&merge);
+        let (combined, _combined) = collect(&combine);
+        letters.emit(&"a".to_string());
+        numbers.emit(&1);
+        numbers.emit(&2);
+        letters.emit(&"b".to_string());
+        assert_eq!(*merged.borrow(), vec!["a", "1", "2", "b"]);
+        assert_eq!(*combined.borrow(), vec![(1, "a".to_string()), (2, "a".to_string()), (2, "b".to_string())]);
+    }
+}
@@ -0,0 +1,425 @@
+//! Observers notified on their own threads.
+//!
+//! A `Subject` updates its observers on the thread publishing the event, hence a slow observer slows the publisher
+//! down. A `ThreadedSubject` runs every observer on a thread of its own instead, and sends it the events through a
+//! bounded channel. When an observer cannot keep up and its channel is full, its `Backpressure` policy decides what
+//! happens: the publisher waits, an event is dropped, or the observer is disconnected.
+//!
+//! Dropping a subscription stops its thread, discarding the events it did not process yet. Shutting the subject down
+//! rather stops accepting events, lets every observer process the events pending in its channel, and waits for all
+//! threads to finish. An observer which panics only stops its own thread: its channel is closed such that publishers
+//! never wait for it, and its account tells it panicked.
+
+use std::collections::VecDeque;
+use std::mem;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, Weak};
+use std::thread::{self, JoinHandle};
+
+use super::Observer;
+
+/// What happens to an event published while the channel of an observer is full.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backpressure {
+    /// The publisher waits until the observer made room in its channel.
+    Block,
+    /// The oldest event of the channel is dropped to make room.
+    DropOldest,
+    /// The event is dropped.
+    DropNewest,
+    /// The observer is disconnected. It still processes the events already in its channel.
+    Disconnect,
+}
+
+/// The account of the events sent to an observer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SubscriberStats {
+    /// The name of the observer.
+    pub name: String,
+    /// The number of events the observer was updated with.
+    pub delivered: usize,
+    /// The number of events dropped because the observer could not keep up.
+    pub dropped: usize,
+    /// Whether the observer was disconnected for not keeping up.
+    pub disconnected: bool,
+    /// Whether the observer panicked, which stopped its thread.
+    pub panicked: bool,
+}
+
+struct State<E> {
+    events: VecDeque<E>,
+    open: bool,
+    stats: SubscriberStats,
+}
+
+/// A bounded channel to the thread of an observer.
+struct Channel<E> {
+    capacity: usize,
+    policy: Backpressure,
+    state: Mutex<State<E>>,
+    changed: Condvar,
+}
+
+impl<E> Channel<E> {
+    fn lock(&self) -> MutexGuard<'_, State<E>> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Sends an event according to the backpressure policy. Returns whether it was queued.
+    fn send(&self, event: E) -> bool {
+        let mut state = self.lock();
+        while state.open && state.events.len() >= self.capacity {
+            match self.policy {
+                Backpressure::Block         => {
+                    state = self.changed.wait(state).unwrap_or_else(|poisoned| poisoned.into_inner());
+                },
+                Backpressure::DropOldest    => {
+                    state.events.pop_front();
+                    state.stats.dropped += 1;
+                },
+                Backpressure::DropNewest    => {
+                    state.stats.dropped += 1;
+                    return false;
+                },
+                Backpressure::Disconnect    => {
+                    state.open = false;
+                    state.stats.disconnected = true;
+                    state.stats.dropped += 1;
+                },
+            }
+        }
+        if !state.open {
+            return false;
+        }
+        state.events.push_back(event);
+        self.changed.notify_all();
+        true
+    }
+
+    /// Waits for the next event. Returns `None` once the channel is closed and empty.
+    fn receive(&self) -> Option<E> {
+        let mut state = self.lock();
+        loop {
+            if let Some(event) = state.events.pop_front() {
+                self.changed.notify_all();
+                return Some(event);
+            }
+            if !state.open {
+                return None;
+            }
+            state = self.changed.wait(state).unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+    }
+
+    /// Stops accepting events, and optionally discards the pending ones.
+    fn close(&self, discard: bool) {
+        let mut state = self.lock();
+        state.open = false;
+        if discard {
+            state.events.clear();
+        }
+        self.changed.notify_all();
+    }
+}
+
+struct Subscriber<E> {
+    id: usize,
+    channel: Arc<Channel<E>>,
+    worker: JoinHandle<()>,
+}
+
+/// The observers of a threaded subject, shared with their subscriptions and threads such that an observer can be
+/// removed once it is unsubscribed or its thread stops.
+struct Subscribers<E> {
+    next_id: usize,
+    active: Vec<Subscriber<E>>,
+    /// The accounts of the observers whose thread stopped by itself, by identifier.
+    exited: Vec<(usize, SubscriberStats)>,
+}
+
+fn lock<E>(subscribers: &Mutex<Subscribers<E>>) -> MutexGuard<'_, Subscribers<E>> {
+    subscribers.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Held by the thread of an observer while it receives events. When the thread stops, even because the observer
+/// panicked, it closes the channel and removes the observer from the subject.
+struct Worker<E> {
+    id: usize,
+    channel: Arc<Channel<E>>,
+    subscribers: Weak<Mutex<Subscribers<E>>>,
+}
+
+impl<E> Drop for Worker<E> {
+    fn drop(&mut self) {
+        if thread::panicking() {
+            self.channel.lock().stats.panicked = true;
+        }
+        self.channel.close(true);
+        if let Some(subscribers) = self.subscribers.upgrade() {
+            let mut subscribers = lock(&subscribers);
+            // The observer is gone already if it was unsubscribed or the subject is shutting down. Otherwise, its
+            // handle is dropped, which detaches this thread as it is about to finish anyway.
+            if let Some(idx) = subscribers.active.iter().position(|subscriber| subscriber.id == self.id) {
+                subscribers.active.remove(idx);
+                let stats = self.channel.lock().stats.clone();
+                subscribers.exited.push((self.id, stats));
+            }
+        }
+    }
+}
+
+/// A handle keeping an observer subscribed to a threaded subject. Dropping it unsubscribes the observer: pending
+/// events are discarded, and it waits for the thread to finish the event it is processing.
+#[must_use = "the observer is unsubscribed as soon as the subscription is dropped"]
+pub struct ThreadedSubscription<E> {
+    id: usize,
+    channel: Arc<Channel<E>>,
+    subscribers: Weak<Mutex<Subscribers<E>>>,
+}
+
+impl<E> ThreadedSubscription<E> {
+    /// Whether the observer still receives events.
+    pub fn is_connected(&self) -> bool {
+        self.channel.lock().open
+    }
+
+    /// The account of the events sent to the observer so far.
+    pub fn stats(&self) -> SubscriberStats {
+        self.channel.lock().stats.clone()
+    }
+}
+
+impl<E> Drop for ThreadedSubscription<E> {
+    fn drop(&mut self) {
+        // The observer is removed before its thread is stopped, which would otherwise account for it as exited.
+        let removed = self.subscribers.upgrade().and_then(|subscribers| {
+            let mut subscribers = lock(&subscribers);
+            let idx = subscribers.active.iter().position(|subscriber| subscriber.id == self.id)?;
+            Some(subscribers.active.remove(idx))
+        });
+        self.channel.close(true);
+        // An observer dropping its own subscription cannot wait for itself.
+        if let Some(subscriber) = removed {
+            if subscriber.worker.thread().id() != thread::current().id() {
+                let _ = subscriber.worker.join();
+            }
+        }
+    }
+}
+
+/// A subject notifying every observer on a thread of its own.
+pub struct ThreadedSubject<E> {
+    subscribers: Arc<Mutex<Subscribers<E>>>,
+}
+
+impl<E> ThreadedSubject<E> where E: Clone + Send + 'static {
+    /// Creates a subject without observers.
+    pub fn new() -> ThreadedSubject<E> {
+        ThreadedSubject {
+            subscribers: Arc::new(Mutex::new(Subscribers { next_id: 0, active: Vec::new(), exited: Vec::new() })),
+        }
+    }
+
+    /// Subscribes an observer, which is updated on a new thread. Up to `capacity` events wait in its channel.
+    ///
+    /// # Panics
+    /// Panics if the capacity is zero.
+    pub fn subscribe<O>(&self, name: &str, capacity: usize, policy: Backpressure, mut observer: O)
+        -> ThreadedSubscription<E> where O: Observer<E> + Send + 'static {
+        assert!(capacity > 0, "the capacity of a channel cannot be zero");
+        let stats = SubscriberStats {
+            name: name.to_string(),
+            delivered: 0,
+            dropped: 0,
+            disconnected: false,
+            panicked: false,
+        };
+        let channel = Arc::new(Channel {
+            capacity,
+            policy,
+            state: Mutex::new(State { events: VecDeque::new(), open: true, stats }),
+            changed: Condvar::new(),
+        });
+        let mut subscribers = self.lock();
+        let id = subscribers.next_id;
+        subscribers.next_id += 1;
+        let receiver = Worker {
+            id,
+            channel: Arc::clone(&channel),
+            subscribers: Arc::downgrade(&self.subscribers),
+        };
+        let worker = thread::spawn(move || {
+            while let Some(event) = receiver.channel.receive() {
+                observer.update(&event);
+                receiver.channel.lock().stats.delivered += 1;
+            }
+        });
+        subscribers.active.push(Subscriber { id, channel: Arc::clone(&channel), worker });
+        ThreadedSubscription { id, channel, subscribers: Arc::downgrade(&self.subscribers) }
+    }
+
+    /// The number of observers receiving events.
+    pub fn len(&self) -> usize {
+        self.lock().active.iter().filter(|subscriber| subscriber.channel.lock().open).count()
+    }
+
+    /// Whether no observer receives events.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Sends an event to all connected observers, and returns how many of them queued it. With the `Block` policy,
+    /// this waits until every such observer has room for the event.
+    pub fn publish(&self, event: E) -> usize {
+        let channels: Vec<Arc<Channel<E>>> = self.lock().active.iter()
+            .map(|subscriber| Arc::clone(&subscriber.channel))
+            .collect();
+        channels.iter().filter(|channel| channel.send(event.clone())).count()
+    }
+
+    /// Stops accepting events, waits for all observers to process their pending events, and returns the account of
+    /// every observer still subscribed, including those whose thread stopped, in the order in which they subscribed.
+    pub fn shutdown(self) -> Vec<SubscriberStats> {
+        let (active, mut stats) = {
+            let mut subscribers = self.lock();
+            (mem::take(&mut subscribers.active), mem::take(&mut subscribers.exited))
+        };
+        for subscriber in &active {
+            subscriber.channel.close(false);
+        }
+        stats.extend(active.into_iter().map(|subscriber| {
+            // A panicking observer only stops its own thread.
+            let _ = subscriber.worker.join();
+            let account = subscriber.channel.lock().stats.clone();
+            (subscriber.id, account)
+        }));
+        stats.sort_by_key(|&(id, _)| id);
+        stats.into_iter().map(|(_, stats)| stats).collect()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Subscribers<E>> {
+        lock(&self.subscribers)
+    }
+}
+
+impl<E> Default for ThreadedSubject<E> where E: Clone + Send + 'static {
+    fn default() -> Self {
+        ThreadedSubject::new()
+    }
+}
+
+/// Dropping the subject without shutting it down lets the threads finish their pending events in the background.
+impl<E> Drop for ThreadedSubject<E> {
+    fn drop(&mut self) {
+        for subscriber in &lock(&self.subscribers).active {
+            subscriber.channel.close(false);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn test_threaded_subject() {
+        let subject = Arc::new(ThreadedSubject::new());
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let subscriptions: Vec<_> = (0..3).map(|idx| {
+            let received = Arc::clone(&received);
+            subject.subscribe(&format!("observer {}", idx), 4, Backpressure::Block, move |event: &u64| {
+                received.lock().unwrap().push((idx, *event, thread::current().id()));
+            })
+        }).collect();
+        assert_eq!(subject.len(), 3);
+
+        // The subject can be shared between publishing threads.
+        let publishers: Vec<_> = (0..2).map(|publisher| {
+            let subject = Arc::clone(&subject);
+            thread::spawn(move || (0..50).for_each(|event| assert_eq!(subject.publish(publisher * 100 + event), 3)))
+        }).collect();
+        publishers.into_iter().for_each(|publisher| publisher.join().unwrap());
+
+        let stats = Arc::try_unwrap(subject).ok().unwrap().shutdown();
+        assert!(stats.iter().all(|stats| stats.delivered == 100 && stats.dropped == 0));
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 300);
+        assert!(received.iter().all(|&(_, _, thread)| thread != thread::current().id()));
+        // Every observer receives the events of a publisher in order.
+        let first: Vec<u64> = received.iter().filter(|&&(idx, event, _)| idx == 0 && event < 100)
+            .map(|&(_, event, _)| event).collect();
+        assert_eq!(first, (0..50).collect::<Vec<_>>());
+        assert!(!subscriptions[0].is_connected());
+    }
+
+    #[test]
+    fn test_backpressure() {
+        let subject = ThreadedSubject::new();
+        let mut gates = Vec::new();
+        let mut logs = Vec::new();
+        let mut subscriptions = Vec::new();
+        let (started, start) = mpsc::channel();
+        for policy in [Backpressure::DropOldest, Backpressure::DropNewest, Backpressure::Disconnect] {
+            let (gate, wait) = mpsc::channel::<()>();
+            let log = Arc::new(Mutex::new(Vec::new()));
+            let (received, started) = (Arc::clone(&log), started.clone());
+            // The observer blocks on its first event until its gate is opened.
+            subscriptions.push(subject.subscribe(&format!("{:?}", policy), 2, policy, move |event: &u32| {
+                if *event == 0 {
+                    started.send(()).unwrap();
+                    wait.recv().unwrap();
+                }
+                received.lock().unwrap().push(*event);
+            }));
+            gates.push(gate);
+            logs.push(log);
+        }
+        subject.publish(0);
+        (0..3).for_each(|_| start.recv().unwrap());
+        assert_eq!(subject.publish(1), 3);
+        assert_eq!(subject.publish(2), 3);
+        assert_eq!(subject.publish(3), 1);
+        assert!(!subscriptions[2].is_connected());
+        assert_eq!(subject.len(), 2);
+        gates.iter().for_each(|gate| gate.send(()).unwrap());
+
+        let stats = subject.shutdown();
+        let dropped: Vec<(usize, usize, bool)> = stats.iter()
+            .map(|stats| (stats.delivered, stats.dropped, stats.disconnected))
+            .collect();
+        assert_eq!(dropped, vec![(3, 1, false), (3, 1, false), (3, 1, true)]);
+        assert_eq!(*logs[0].lock().unwrap(), vec![0, 2, 3]);
+        assert_eq!(*logs[1].lock().unwrap(), vec![0, 1, 2]);
+        assert_eq!(*logs[2].lock().unwrap(), vec![0, 1, 2]);
+        assert_eq!(subscriptions[0].stats(), stats[0]);
+    }
+
+    #[test]
+    fn test_panicking_observer() {
+        let subject = ThreadedSubject::new();
+        let failing = subject.subscribe("failing", 1, Backpressure::Block, |event: &u32| {
+            assert_ne!(*event, 1, "the observer cannot handle 1");
+        });
+        let (sent, received) = mpsc::channel();
+        let working = subject.subscribe("working", 1, Backpressure::Block, move |event: &u32| {
+            sent.send(*event).unwrap();
+        });
+
+        // The publisher does not wait for the observer which panicked.
+        (0..10).for_each(|event| {
+            subject.publish(event);
+        });
+        assert_eq!(received.iter().take(10).collect::<Vec<_>>(), (0..10).collect::<Vec<_>>());
+        assert!(!failing.is_connected());
+        assert!(failing.stats().panicked);
+        assert_eq!(subject.len(), 1);
+
+        // Dropping a subscription removes the observer.
+        drop(working);
+        assert!(subject.is_empty());
+        let stats = subject.shutdown();
+        assert_eq!(stats.len(), 1);
+        assert_eq!((stats[0].name.as_str(), stats[0].delivered, stats[0].panicked), ("failing", 1, true));
+    }
+}
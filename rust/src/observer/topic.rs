@@ -0,0 +1,345 @@
+//! Topic-based publish/subscribe.
+//!
+//! Rather than notifying every observer of every event, a `Broker` routes messages by topic. Topics are hierarchical
+//! names whose levels are separated by dots, such as `sensor.kitchen.temp`. Observers subscribe with a pattern, in
+//! which `*` matches exactly one level and `#` matches any number of levels (including none) at the end of the topic:
+//! `sensor.*.temp` matches the temperature of every room, and `sensor.#` matches all sensor messages, as well as
+//! `sensor` itself. Subscriptions can further filter messages on their payload.
+//!
+//! Patterns are stored in a tree with one level per node, hence finding the subscribers of a topic only visits the
+//! nodes matching its levels, however many subscriptions there are.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::rc::Rc;
+use std::result;
+use std::str::FromStr;
+
+use super::{Observable, Observer, Slot, Subscription};
+
+/// Result of parsing topics and patterns.
+pub type Result<T> = result::Result<T, TopicError>;
+
+/// Errors raised by invalid topics and patterns.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TopicError {
+    /// A topic to publish to is invalid.
+    InvalidTopic {
+        /// The topic.
+        topic: String,
+        /// What is wrong with it.
+        reason: &'static str,
+    },
+    /// A pattern to subscribe with is invalid.
+    InvalidPattern {
+        /// The pattern.
+        pattern: String,
+        /// What is wrong with it.
+        reason: &'static str,
+    },
+}
+
+impl fmt::Display for TopicError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TopicError::InvalidTopic { ref topic, reason }      => write!(f, "invalid topic '{}': {}", topic, reason),
+            TopicError::InvalidPattern { ref pattern, reason }  => {
+                write!(f, "invalid pattern '{}': {}", pattern, reason)
+            },
+        }
+    }
+}
+
+impl Error for TopicError {}
+
+/// A pattern matching topics, such as `sensor.*.temp` or `sensor.#`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Pattern {
+    levels: Vec<String>,
+}
+
+impl Pattern {
+    /// Whether the pattern matches a topic.
+    pub fn matches(&self, topic: &str) -> bool {
+        fn matches(pattern: &[String], topic: &[&str]) -> bool {
+            match (pattern.split_first(), topic.split_first()) {
+                (Some((level, _)), _) if level == "#"       => true,
+                (Some((level, pattern)), Some((name, topic)))  => {
+                    (level == "*" || level == name) && matches(pattern, topic)
+                },
+                (None, None)                                => true,
+                _                                           => false,
+            }
+        }
+        matches(&self.levels, &topic.split('.').collect::<Vec<_>>())
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = TopicError;
+
+    fn from_str(pattern: &str) -> Result<Pattern> {
+        let invalid = |reason| TopicError::InvalidPattern { pattern: pattern.to_string(), reason };
+        let levels: Vec<String> = pattern.split('.').map(String::from).collect();
+        if levels.iter().any(String::is_empty) {
+            return Err(invalid("levels cannot be empty"));
+        }
+        if levels.iter().any(|level| level.len() > 1 && (level.contains('*') || level.contains('#'))) {
+            return Err(invalid("wildcards must make up a whole level"));
+        }
+        if levels.iter().rev().skip(1).any(|level| level == "#") {
+            return Err(invalid("'#' can only be the last level"));
+        }
+        Ok(Pattern { levels })
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(&self.levels.join("."))
+    }
+}
+
+/// Splits a topic into its levels.
+fn levels(topic: &str) -> Result<Vec<&str>> {
+    let invalid = |reason| TopicError::InvalidTopic { topic: topic.to_string(), reason };
+    let levels: Vec<&str> = topic.split('.').collect();
+    if levels.iter().any(|level| level.is_empty()) {
+        return Err(invalid("levels cannot be empty"));
+    }
+    if levels.iter().any(|level| level.contains('*') || level.contains('#')) {
+        return Err(invalid("wildcards are only allowed in patterns"));
+    }
+    Ok(levels)
+}
+
+/// A message published to a topic.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Message<E> {
+    /// The topic the message was published to.
+    pub topic: String,
+    /// The content of the message.
+    pub payload: E,
+}
+
+/// A node of the subscription tree, for one level of patterns.
+#[derive(Default)]
+struct Node {
+    children: HashMap<String, Node>,
+    /// Subscriptions whose pattern ends at this level.
+    exact: Vec<usize>,
+    /// Subscriptions whose pattern continues with `#` after this level.
+    descendants: Vec<usize>,
+}
+
+impl Node {
+    fn insert(&mut self, levels: &[String], id: usize) {
+        match levels.split_first() {
+            Some((level, _)) if level == "#"   => self.descendants.push(id),
+            Some((level, rest))                 => self.children.entry(level.clone()).or_default().insert(rest, id),
+            None                                => self.exact.push(id),
+        }
+    }
+
+    /// Removes a subscription, and returns whether the node is left empty.
+    fn remove(&mut self, levels: &[String], id: usize) -> bool {
+        match levels.split_first() {
+            Some((level, _)) if level == "#"   => self.descendants.retain(|&other| other != id),
+            Some((level, rest))                 => {
+                if self.children.get_mut(level).is_some_and(|child| child.remove(rest, id)) {
+                    self.children.remove(level);
+                }
+            },
+            None                                => self.exact.retain(|&other| other != id),
+        }
+        self.children.is_empty() && self.exact.is_empty() && self.descendants.is_empty()
+    }
+
+    fn collect(&self, levels: &[&str], ids: &mut Vec<usize>) {
+        ids.extend(&self.descendants);
+        match levels.split_first() {
+            Some((level, rest)) => {
+                for child in [self.children.get(*level), self.children.get("*")].into_iter().flatten() {
+                    child.collect(rest, ids);
+                }
+            },
+            None                => ids.extend(&self.exact),
+        }
+    }
+}
+
+/// A predicate on the payload of messages.
+pub type Filter<E> = Box<dyn Fn(&E) -> bool>;
+
+/// A subscriber of a broker.
+struct Entry<E> {
+    filter: Option<Filter<E>>,
+    observer: RefCell<Box<dyn Observer<Message<E>>>>,
+}
+
+struct Subscriptions<E> {
+    root: Node,
+    next: usize,
+    /// The subscribers by identifier, which are looked up for every message delivered.
+    entries: HashMap<usize, Rc<Slot<Entry<E>>>>,
+}
+
+/// Routes messages to the observers subscribed to their topic.
+pub struct Broker<E> {
+    subscriptions: Rc<RefCell<Subscriptions<E>>>,
+}
+
+impl<E> Broker<E> where E: 'static {
+    /// Creates a broker without subscriptions.
+    pub fn new() -> Broker<E> {
+        let subscriptions = Subscriptions { root: Node::default(), next: 0, entries: HashMap::new() };
+        Broker {
+            subscriptions: Rc::new(RefCell::new(subscriptions)),
+        }
+    }
+
+    /// The number of subscriptions.
+    pub fn len(&self) -> usize {
+        self.subscriptions.borrow().entries.len()
+    }
+
+    /// Whether there is no subscription.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Subscribes an observer to the topics matching a pattern.
+    pub fn subscribe<O>(&self, pattern: &str, observer: O) -> Result<Subscription>
+        where O: Observer<Message<E>> + 'static {
+        Ok(self.add(pattern.parse()?, None, Box::new(observer)))
+    }
+
+    /// Subscribes an observer to the messages of the topics matching a pattern, whose payload satisfies a filter.
+    pub fn subscribe_filtered<F, O>(&self, pattern: &str, filter: F, observer: O) -> Result<Subscription>
+        where F: Fn(&E) -> bool + 'static, O: Observer<Message<E>> + 'static {
+        Ok(self.add(pattern.parse()?, Some(Box::new(filter)), Box::new(observer)))
+    }
+
+    /// Publishes a message to a topic, and returns the number of observers it was delivered to.
+    ///
+    /// Observers are notified in the order in which they subscribed. As with `Subject`, they may subscribe or
+    /// unsubscribe others during the notification, and are not notified of messages they publish themselves.
+    pub fn publish(&self, topic: &str, payload: E) -> Result<usize> {
+        let levels = levels(topic)?;
+        let entries: Vec<Rc<Slot<Entry<E>>>> = {
+            let subscriptions = self.subscriptions.borrow();
+            let mut ids = Vec::new();
+            subscriptions.root.collect(&levels, &mut ids);
+            ids.sort_unstable();
+            ids.into_iter().filter_map(|id| subscriptions.entries.get(&id).cloned()).collect()
+        };
+        let message = Message { topic: topic.to_string(), payload };
+        let mut delivered = 0;
+        for entry in entries {
+            if !entry.is_active() || !entry.filter.as_ref().is_none_or(|filter| filter(&message.payload)) {
+                continue;
+            }
+            if let Ok(mut observer) = entry.observer.try_borrow_mut() {
+                observer.update(&message);
+                delivered += 1;
+            }
+        }
+        Ok(delivered)
+    }
+
+    fn add(&self, pattern: Pattern, filter: Option<Filter<E>>, observer: Box<dyn Observer<Message<E>>>)
+        -> Subscription {
+        let id = {
+            let mut subscriptions = self.subscriptions.borrow_mut();
+            let id = subscriptions.next;
+            subscriptions.next += 1;
+            subscriptions.entries.insert(id, Slot::new(id, Entry { filter, observer: RefCell::new(observer) }));
+            subscriptions.root.insert(&pattern.levels, id);
+            id
+        };
+        Subscription::cancelling(&self.subscriptions, move |subscriptions| {
+            subscriptions.root.remove(&pattern.levels, id);
+            subscriptions.entries.remove(&id).inspect(|entry| entry.deactivate())
+        })
+    }
+}
+
+impl<E> Default for Broker<E> where E: 'static {
+    fn default() -> Self {
+        Broker::new()
+    }
+}
+
+/// Registering an observer subscribes it to all topics.
+impl<E> Observable<Message<E>> for Broker<E> where E: 'static {
+    fn register(&self, observer: Box<dyn Observer<Message<E>>>) -> Subscription {
+        self.add(Pattern { levels: vec![String::from("#")] }, None, observer)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(log: &Rc<RefCell<Vec<String>>>, name: &'static str) -> impl FnMut(&Message<f64>) {
+        let log = Rc::clone(log);
+        move |message: &Message<f64>| log.borrow_mut().push(format!("{} {} {}", name, message.topic, message.payload))
+    }
+
+    #[test]
+    fn test_topics() {
+        let broker = Broker::new();
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let _temperatures = broker.subscribe("sensor.*.temp", record(&log, "temp")).unwrap();
+        let _sensors = broker.subscribe("sensor.#", record(&log, "all")).unwrap();
+        let kitchen = broker.subscribe("sensor.kitchen.temp", record(&log, "kitchen")).unwrap();
+        let _hot = broker.subscribe_filtered("#", |&value: &f64| value > 30.0, record(&log, "hot")).unwrap();
+
+        assert_eq!(broker.publish("sensor.kitchen.temp", 21.5), Ok(3));
+        assert_eq!(broker.publish("sensor.garage.temp", 35.0), Ok(3));
+        assert_eq!(broker.publish("sensor.kitchen.humidity", 40.0), Ok(2));
+        assert_eq!(broker.publish("sensor", 0.0), Ok(1));
+        assert_eq!(broker.publish("alarm.kitchen.temp", 20.0), Ok(0));
+        assert_eq!(*log.borrow(), vec![
+            "temp sensor.kitchen.temp 21.5", "all sensor.kitchen.temp 21.5", "kitchen sensor.kitchen.temp 21.5",
+            "temp sensor.garage.temp 35", "all sensor.garage.temp 35", "hot sensor.garage.temp 35",
+            "all sensor.kitchen.humidity 40", "hot sensor.kitchen.humidity 40",
+            "all sensor 0",
+        ]);
+
+        drop(kitchen);
+        assert_eq!(broker.len(), 3);
+        assert_eq!(broker.publish("sensor.kitchen.temp", 22.0), Ok(2));
+
+        let error = |pattern: &str| broker.subscribe(pattern, |_: &Message<f64>| ()).unwrap_err().to_string();
+        assert_eq!(error("sensor.#.temp"), "invalid pattern 'sensor.#.temp': '#' can only be the last level");
+        assert_eq!(error("sensor..temp"), "invalid pattern 'sensor..temp': levels cannot be empty");
+        assert_eq!(error("sensor.kit*"), "invalid pattern 'sensor.kit*': wildcards must make up a whole level");
+        assert_eq!(broker.publish("sensor.*", 1.0).unwrap_err().to_string(),
+                   "invalid topic 'sensor.*': wildcards are only allowed in patterns");
+        assert!("sensor.*.temp".parse::<Pattern>().unwrap().matches("sensor.attic.temp"));
+    }
+
+    #[test]
+    fn test_many_subscriptions() {
+        let broker: Broker<f64> = Broker::new();
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut subscriptions: Vec<Subscription> = (0..5000)
+            .map(|room| broker.subscribe(&format!("sensor.room{}.temp", room), record(&log, "room")).unwrap())
+            .collect();
+        subscriptions.push(broker.subscribe("sensor.*.temp", record(&log, "any")).unwrap());
+        subscriptions.push(broker.subscribe("sensor.room42.#", record(&log, "room42")).unwrap());
+
+        assert_eq!(broker.publish("sensor.room42.temp", 19.0), Ok(3));
+        assert_eq!(broker.publish("sensor.room4999.temp", 19.0), Ok(2));
+        assert_eq!(broker.publish("sensor.room5000.temp", 19.0), Ok(1));
+
+        subscriptions.truncate(10);
+        assert_eq!(broker.len(), 10);
+        assert_eq!(broker.subscriptions.borrow().root.children["sensor"].children.len(), 10);
+        assert_eq!(broker.publish("sensor.room42.temp", 19.0), Ok(0));
+    }
+}
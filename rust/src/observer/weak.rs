@@ -0,0 +1,143 @@
+//! Observers held through weak references.
+//!
+//! A `Subject` owns its observers, which suits observers created for the subject. Views usually have a life of their
+//! own though: the UI creates and destroys them, and the subject should neither keep them alive nor require them to
+//! unsubscribe explicitly. A `WeakSubject` only holds `Weak` references to observers shared through `Rc`. Observers
+//! which were dropped are pruned when notifications are sent, and `live_count()` tells how many are still alive.
+
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+
+use super::{Observable, Observer, Slots, Subscription};
+
+/// An observer shared with a weak subject.
+pub type SharedObserver<E> = Rc<RefCell<dyn Observer<E>>>;
+
+/// A subject holding weak references to its observers.
+pub struct WeakSubject<E> {
+    observers: RefCell<Slots<Weak<RefCell<dyn Observer<E>>>>>,
+}
+
+impl<E> WeakSubject<E> where E: 'static {
+    /// Creates a subject without observers.
+    pub fn new() -> WeakSubject<E> {
+        WeakSubject {
+            observers: RefCell::new(Slots::new()),
+        }
+    }
+
+    /// Attaches an observer without keeping it alive. Attaching an observer twice has no effect.
+    pub fn attach<O>(&self, observer: &Rc<RefCell<O>>) where O: Observer<E> + 'static {
+        let observer: SharedObserver<E> = observer.clone();
+        let weak = Rc::downgrade(&observer);
+        let mut observers = self.observers.borrow_mut();
+        if !observers.iter().any(|attached| attached.ptr_eq(&weak)) {
+            observers.push(weak);
+        }
+    }
+
+    /// Detaches an observer. Returns whether it was attached.
+    pub fn detach<O>(&self, observer: &Rc<RefCell<O>>) -> bool where O: Observer<E> + 'static {
+        let observer: SharedObserver<E> = observer.clone();
+        let weak = Rc::downgrade(&observer);
+        self.observers.borrow_mut().retain(|attached| !attached.ptr_eq(&weak)) > 0
+    }
+
+    /// The number of attached observers which are still alive.
+    pub fn live_count(&self) -> usize {
+        self.observers.borrow().iter().filter(|observer| observer.strong_count() > 0).count()
+    }
+
+    /// Notifies the live observers, in the order in which they were attached, after forgetting the dropped ones.
+    /// Returns how many observers were updated.
+    ///
+    /// Like with `Subject`, observers may attach or detach others while being notified, and an observer notifying the
+    /// subject it observes is not updated again with the nested event.
+    pub fn notify(&self, event: &E) -> usize {
+        let slots = {
+            let mut observers = self.observers.borrow_mut();
+            observers.retain(|observer| observer.strong_count() > 0);
+            observers.snapshot()
+        };
+        let mut updated = 0;
+        // Observers detached by earlier ones are skipped.
+        for observer in slots.iter().filter(|slot| slot.is_active()).filter_map(|slot| slot.upgrade()) {
+            if let Ok(mut observer) = observer.try_borrow_mut() {
+                observer.update(event);
+                updated += 1;
+            }
+        }
+        updated
+    }
+}
+
+impl<E> Default for WeakSubject<E> where E: 'static {
+    fn default() -> Self {
+        WeakSubject::new()
+    }
+}
+
+/// Registering an observer gives it to the subscription: the observer lives as long as the subscription does.
+impl<E> Observable<E> for WeakSubject<E> where E: 'static {
+    fn register(&self, mut observer: Box<dyn Observer<E>>) -> Subscription {
+        let observer = Rc::new(RefCell::new(move |event: &E| observer.update(event)));
+        self.attach(&observer);
+        Subscription::new(move || drop(observer))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::View;
+
+    #[test]
+    fn test_weak_subject() {
+        let subject = WeakSubject::new();
+        let main = Rc::new(RefCell::new(View::new("main")));
+        subject.attach(&main);
+        subject.attach(&main);
+        {
+            let popup = Rc::new(RefCell::new(View::new("popup")));
+            subject.attach(&popup);
+            assert_eq!(subject.live_count(), 2);
+            assert_eq!(subject.notify(&1), 2);
+            assert_eq!(popup.borrow().lines(), vec!["View popup got data: 1"]);
+        }
+        // The popup was dropped with its scope, the subject did not keep it alive.
+        assert_eq!(subject.live_count(), 1);
+        assert_eq!(subject.notify(&2), 1);
+        assert_eq!(main.borrow().lines(), vec!["View main got data: 1", "View main got data: 2"]);
+
+        assert!(subject.detach(&main));
+        assert!(!subject.detach(&main));
+        assert_eq!(subject.notify(&3), 0);
+        assert_eq!(Rc::strong_count(&main), 1);
+    }
+
+    #[test]
+    fn test_weak_subject_subscription() {
+        let subject: Rc<WeakSubject<u64>> = Rc::new(WeakSubject::new());
+        let view = View::new("owned");
+        let subscription = subject.subscribe(view.clone());
+        assert_eq!(subject.notify(&5), 1);
+
+        // An observer detaching another one during the notification.
+        let other = Rc::new(RefCell::new(View::new("other")));
+        let (source, target) = (Rc::downgrade(&subject), Rc::downgrade(&other));
+        let detacher = Rc::new(RefCell::new(move |_: &u64| {
+            if let (Some(subject), Some(other)) = (source.upgrade(), target.upgrade()) {
+                subject.detach(&other);
+            }
+        }));
+        subject.attach(&detacher);
+        subject.attach(&other);
+        assert_eq!(subject.notify(&6), 2);
+        assert!(other.borrow().lines().is_empty());
+
+        drop(subscription);
+        assert_eq!(subject.live_count(), 1);
+        assert_eq!(view.lines(), vec!["View owned got data: 5", "View owned got data: 6"]);
+    }
+}
@@ -13,7 +13,7 @@
 //! then simplifies the initialisation process as classes needn't be instantiated manually.
 //!
 //! # Participants
-//! - `Protoptype`: declares a `clone()` interface for cloning itself.
+//! - `Prototype`: declares a `clone()` interface for cloning itself.
 //! - `ConcretePrototype`: implements the `Prototype` interface.
 //! - `Client`: creates new objects by cloning the prototype rather than calling constructors.
 //!
@@ -37,6 +37,7 @@
 #[cfg(test)]
 mod tests {
     #[test]
+    #[allow(clippy::assertions_on_constants)]
     fn test_prototype() {
         assert!(true);
     }
@@ -28,15 +28,25 @@
 //!
 //! # Known Uses
 //! Smart pointers and copy on write objects.
+//!
+//! # Example
+//! ```
+//! use patterns::proxy::{Car, ICar, ProxyCar};
+//!
+//! let car = Car;
+//! assert_eq!(ProxyCar::new(17, &car).drive(), "driver is too young");
+//! assert_eq!(ProxyCar::new(30, &car).drive(), "car is driving");
+//! ```
 
 
 /// An interface for a Car
-trait ICar {
+pub trait ICar {
+    /// Drives the car.
     fn drive(&self) -> String;
 }
 
 /// A simple car
-struct Car;
+pub struct Car;
 
 impl ICar for Car {
     /// Return that the car is driving
@@ -46,8 +56,8 @@ impl ICar for Car {
 }
 
 /// The proxy of the car
-struct ProxyCar<'a> {
-    real_car: &'a ICar,
+pub struct ProxyCar<'a> {
+    real_car: &'a dyn ICar,
     driver_age: i32,
 }
 
@@ -63,7 +73,7 @@ impl<'a> ICar for ProxyCar<'a> {
 
 impl<'a> ProxyCar<'a> {
     /// Constructor
-    fn new(driver_age: i32, other_car: &'a ICar) -> ProxyCar<'a> {
+    pub fn new(driver_age: i32, other_car: &'a dyn ICar) -> ProxyCar<'a> {
         ProxyCar {
             real_car: other_car,
             driver_age,
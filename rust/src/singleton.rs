@@ -34,26 +34,42 @@
 //! - Cache providing fast access to objects used often - a single instance controls the cache.
 //! - Only a single instance should access some hardware item.
 //! - State pattern instances are usually implemented as singletons.
+//!
+//! # Example
+//! ```
+//! use patterns::singleton::get_instance;
+//!
+//! *get_instance().lock() = 42;
+//! assert_eq!(*get_instance().lock(), 42);
+//! ```
 
 
 // The following code is copied from stack exchange
-use std::sync::{Arc, Mutex, Once, ONCE_INIT};
-use std::mem;
+use std::sync::{Arc, Mutex, MutexGuard, Once};
+use std::ptr;
 
+/// A handle to the unique instance, it can be cloned and shared across threads.
 #[derive(Clone)]
-struct SingletonReader {
+pub struct SingletonReader {
     // Since we will be used in many threads, we need to protect
     // concurrent access
     inner: Arc<Mutex<u8>>,
 }
 
+impl SingletonReader {
+    /// Locks the data held by the singleton.
+    pub fn lock(&self) -> MutexGuard<'_, u8> {
+        self.inner.lock().unwrap()
+    }
+}
+
 
 /// Note that by the inner workings of Rust, one cannot make this `get_instance()` class to be part of `SingletonReader`.
 /// Moreover, note that this entire code can be simplied significantly using the `lazy-static` crate.
-fn get_instance() -> SingletonReader {
+pub fn get_instance() -> SingletonReader {
     // Initialize it to a null value
-    static mut SINGLETON: *const SingletonReader = 0 as *const SingletonReader;
-    static ONCE: Once = ONCE_INIT;
+    static mut SINGLETON: *const SingletonReader = ptr::null();
+    static ONCE: Once = Once::new();
 
     unsafe {
         ONCE.call_once(|| {
@@ -63,7 +79,7 @@ fn get_instance() -> SingletonReader {
             };
 
             // Put it in the heap so it can outlive this call
-            SINGLETON = mem::transmute(Box::new(singleton));
+            SINGLETON = Box::into_raw(Box::new(singleton));
         });
 
         // Now we give out a copy of the data that is safe to use concurrently.
@@ -29,6 +29,11 @@
 //! Creating state objects ahead of time and never destroying thme saves time if the state transitions occur rapidly,
 //! however the context must hold references to all of them.
 //!
+//! Requests which do not apply to the current state can either be ignored or refused. Ignoring them hides mistakes
+//! from the client, hence here every transition returns a `TransitionError` naming the state and the refused action.
+//! The `State` interface refuses all actions by default, such that every concrete state only implements the
+//! transitions it allows.
+//!
 //! # Known Uses
 //! - "tools" in drawing programs
 //! - JDRAW: Handles, SnapToGrid
@@ -36,50 +41,142 @@
 //!
 //! # Note
 //! The following code is taken from the official Rust book.
+//!
+//! # Example
+//! ```
+//! use patterns::state::{Action, Post};
+//!
+//! let mut post = Post::new();
+//! post.add_text("Hello world").unwrap();
+//! post.request_review().unwrap();
+//! assert_eq!(post.content(), "");
+//! assert_eq!(post.add_text("!").unwrap_err().action, Action::Edit);
+//! post.approve().unwrap();
+//! assert_eq!(post.content(), "Hello world");
+//! ```
+
+use std::error::Error;
+use std::fmt;
+use std::result;
+
+/// Result of a transition.
+pub type Result<T> = result::Result<T, TransitionError>;
+
+/// An action requested on a post.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// Editing the content.
+    Edit,
+    /// Submitting the post for review.
+    RequestReview,
+    /// Approving the post.
+    Approve,
+    /// Rejecting the post, sending it back to draft.
+    Reject,
+}
 
+impl Action {
+    /// The action as a verb, in lowercase.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Action::Edit            => "edit",
+            Action::RequestReview   => "request a review of",
+            Action::Approve         => "approve",
+            Action::Reject          => "reject",
+        }
+    }
+}
 
-/// The state interface
-trait State {
-    fn request_review(self: Box<Self>) -> Box<State>;
-    fn approve(self: Box<Self>) -> Box<State>;
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+/// Error raised when an action does not apply to the current state.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TransitionError {
+    /// The name of the state of the post.
+    pub state: &'static str,
+    /// The refused action.
+    pub action: Action,
+}
+
+impl fmt::Display for TransitionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "cannot {} a post in state {}", self.action, self.state)
+    }
+}
+
+impl Error for TransitionError {}
+
+/// The state interface. All actions are refused unless a state allows them.
+pub trait State {
+    /// The name of the state.
+    fn name(&self) -> &'static str;
+    /// Whether the content can be edited.
+    fn edit(&self) -> Result<()> {
+        Err(self.refuse(Action::Edit))
+    }
+    /// Transition requested when the post is submitted for review.
+    fn request_review(&self) -> Result<Box<dyn State>> {
+        Err(self.refuse(Action::RequestReview))
+    }
+    /// Transition requested when the post is approved.
+    fn approve(&self) -> Result<Box<dyn State>> {
+        Err(self.refuse(Action::Approve))
+    }
+    /// Transition requested when the post is rejected.
+    fn reject(&self) -> Result<Box<dyn State>> {
+        Err(self.refuse(Action::Reject))
+    }
+    /// The content of the post visible in this state.
     fn content<'a>(&self, _post: &'a Post) -> &'a str {
         ""
     }
+    /// The error refusing an action in this state.
+    fn refuse(&self, action: Action) -> TransitionError {
+        TransitionError { state: self.name(), action }
+    }
 }
 
 /// A concrete state.
-struct Draft;
+pub struct Draft;
 impl State for Draft {
-    fn request_review(self: Box<Self>) -> Box<State> {
-        Box::new(PendingReview)
+    fn name(&self) -> &'static str {
+        "Draft"
+    }
+
+    fn edit(&self) -> Result<()> {
+        Ok(())
     }
 
-    fn approve(self: Box<Self>) -> Box<State> {
-        self
+    fn request_review(&self) -> Result<Box<dyn State>> {
+        Ok(Box::new(PendingReview))
     }
 }
 
 /// A concrete state.
-struct PendingReview;
+pub struct PendingReview;
 impl State for PendingReview {
-    fn request_review(self: Box<Self>) -> Box<State> {
-        self
+    fn name(&self) -> &'static str {
+        "PendingReview"
     }
 
-    fn approve(self: Box<Self>) -> Box<State> {
-        Box::new(Published)
+    fn approve(&self) -> Result<Box<dyn State>> {
+        Ok(Box::new(Published))
+    }
+
+    fn reject(&self) -> Result<Box<dyn State>> {
+        Ok(Box::new(Draft))
     }
 }
 
 /// A concrete state.
-struct Published;
+pub struct Published;
 impl State for Published {
-    fn request_review(self: Box<Self>) -> Box<State> {
-        self
-    }
-
-    fn approve(self: Box<Self>) -> Box<State> {
-        self
+    fn name(&self) -> &'static str {
+        "Published"
     }
 
     fn content<'a>(&self, post: &'a Post) -> &'a str {
@@ -89,37 +186,59 @@ impl State for Published {
 
 
 /// The state machine
-struct Post {
-    state: Option<Box<State>>,
+pub struct Post {
+    state: Box<dyn State>,
     content: String,
 }
 
 impl Post {
-    fn new() -> Post {
+    /// Creates an empty draft.
+    pub fn new() -> Post {
         Post {
-            state: Some(Box::new(Draft)),
+            state: Box::new(Draft),
             content: String::new(),
         }
     }
 
-    fn add_text(&mut self, text: &str) {
+    /// Appends text to the post. Only drafts can be edited.
+    pub fn add_text(&mut self, text: &str) -> Result<()> {
+        self.state.edit()?;
         self.content.push_str(text);
+        Ok(())
     }
 
-    fn content(&self) -> &str {
-        self.state.as_ref().unwrap().content(&self)
+    /// Returns the name of the current state.
+    pub fn state(&self) -> &'static str {
+        self.state.name()
     }
 
-    fn request_review(&mut self) {
-        if let Some(s) = self.state.take() {
-            self.state = Some(s.request_review())
-        }
+    /// Returns the content of the post if it is published.
+    pub fn content(&self) -> &str {
+        self.state.content(self)
     }
 
-    fn approve(&mut self) {
-        if let Some(s) = self.state.take() {
-            self.state = Some(s.approve())
-        }
+    /// Submits the post for review.
+    pub fn request_review(&mut self) -> Result<()> {
+        self.state = self.state.request_review()?;
+        Ok(())
+    }
+
+    /// Approves the post.
+    pub fn approve(&mut self) -> Result<()> {
+        self.state = self.state.approve()?;
+        Ok(())
+    }
+
+    /// Rejects the post, which becomes a draft again.
+    pub fn reject(&mut self) -> Result<()> {
+        self.state = self.state.reject()?;
+        Ok(())
+    }
+}
+
+impl Default for Post {
+    fn default() -> Self {
+        Post::new()
     }
 }
 
@@ -132,15 +251,38 @@ mod tests {
     fn test_state() {
         let mut post = Post::new();
 
-        post.add_text("I ate a salad for lunch today");
+        post.add_text("I ate a salad for lunch today").unwrap();
         assert_eq!("", post.content());
 
-        post.request_review();
+        post.request_review().unwrap();
         assert_eq!("", post.content());
 
-        post.approve();
+        post.approve().unwrap();
         assert_eq!("I ate a salad for lunch today", post.content());
     }
+
+    #[test]
+    fn test_refused_transitions() {
+        let mut post = Post::new();
+        assert_eq!(post.approve(), Err(TransitionError { state: "Draft", action: Action::Approve }));
+        assert_eq!(post.reject().unwrap_err().to_string(), "cannot reject a post in state Draft");
+        assert_eq!(post.state(), "Draft");
+
+        post.add_text("I ate a salad").unwrap();
+        post.request_review().unwrap();
+        assert_eq!(post.request_review().unwrap_err().to_string(),
+                   "cannot request a review of a post in state PendingReview");
+        assert_eq!(post.add_text(" for lunch").unwrap_err().action, Action::Edit);
+
+        // A rejected post goes back to draft, where it can be edited again.
+        post.reject().unwrap();
+        post.add_text(" for lunch today").unwrap();
+        post.request_review().unwrap();
+        post.approve().unwrap();
+        assert_eq!(post.content(), "I ate a salad for lunch today");
+        assert_eq!(post.add_text("!"), Err(TransitionError { state: "Published", action: Action::Edit }));
+        assert_eq!(post.approve().unwrap_err().state, "Published");
+    }
 }
 
 
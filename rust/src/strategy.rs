@@ -42,16 +42,31 @@
 //! usually provides only very few public methods, whereas in state machines the list of public methods can be quite
 //! extensive. On top of that, states can contain state-specific data and methods, which should not be the case of
 //! strategies.
+//!
+//! # Example
+//! ```
+//! use patterns::strategy::{Algorithm, SomeObject};
+//!
+//! struct NoopAlgorithm;
+//! impl Algorithm for NoopAlgorithm {
+//!     fn run(&self) -> &str {
+//!         "nothing to do"
+//!     }
+//! }
+//!
+//! let object = SomeObject::new(Box::new(NoopAlgorithm));
+//! assert_eq!(object.run(), "nothing to do");
+//! ```
 
 
 /// An interface trait that all algorithms implement
-trait Algorithm {
+pub trait Algorithm {
     /// Run the algorithm
     fn run(&self) -> &str;
 }
 
 /// Concrete algorithm
-struct FastAlgorithm;
+pub struct FastAlgorithm;
 impl Algorithm for FastAlgorithm {
     fn run(&self) -> &str {
         "very fast algorithm"
@@ -60,7 +75,7 @@ impl Algorithm for FastAlgorithm {
 
 
 /// Another concrete algorithm
-struct SlowAlgorithm;
+pub struct SlowAlgorithm;
 impl Algorithm for SlowAlgorithm {
     fn run(&self) -> &str {
         "very slow algorithm ..."
@@ -69,22 +84,25 @@ impl Algorithm for SlowAlgorithm {
 
 
 /// Concrete object whose behaviour is modified based on the attached algorithm
-struct SomeObject {
-    behaviour: Box<Algorithm>,
+pub struct SomeObject {
+    behaviour: Box<dyn Algorithm>,
 }
 
 impl SomeObject {
-    fn new(alg: Box<Algorithm>) -> SomeObject {
+    /// Creates an object using the given algorithm.
+    pub fn new(alg: Box<dyn Algorithm>) -> SomeObject {
         SomeObject {
             behaviour: alg,
         }
     }
 
-    fn set_behaviour(&mut self, alg: Box<Algorithm>) {
+    /// Replaces the algorithm at runtime.
+    pub fn set_behaviour(&mut self, alg: Box<dyn Algorithm>) {
         self.behaviour = alg;
     }
 
-    fn run(&self) -> &str {
+    /// Runs the currently selected algorithm.
+    pub fn run(&self) -> &str {
         self.behaviour.run()
     }
 }
@@ -25,24 +25,41 @@
 //! # Known Uses
 //! - Can be fundamentally found in almost all abstract classes.
 //! - This is important in class libraries
+//!
+//! # Example
+//! ```
+//! use patterns::template_method::Object;
+//!
+//! let mut objects = [Object::new("heavy", 2.0), Object::new("light", 1.0)];
+//! objects.sort();
+//! assert_eq!(objects[0].name(), "light");
+//! ```
 
 
 use std::cmp::Ordering;
 
-struct Object {
-    name: &'static str,
+/// An object sorted by weight, then by name.
+pub struct Object {
+    name: String,
     weight: f32,
 }
 
 impl Object {
-    fn new(name: &'static str, weight: f32) -> Object {
+    /// Creates an object.
+    pub fn new<S>(name: S, weight: f32) -> Object where S: Into<String> {
         Object {
-            name,
+            name: name.into(),
             weight,
         }
     }
 
-    fn print(&self) -> String {
+    /// The name of the object.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Describes the object.
+    pub fn print(&self) -> String {
         format!("{} with weight {}", self.name, self.weight)
     }
 }
@@ -51,10 +68,7 @@ impl Object {
 impl PartialEq for Object {
     #[inline]
     fn eq(&self, other: &Object) -> bool {
-        match self.cmp(other) {
-            Ordering::Equal => true,
-            _               => false,
-        }
+        matches!(self.cmp(other), Ordering::Equal)
     }
 }
 
@@ -65,10 +79,7 @@ impl Eq for Object {}
 impl PartialOrd for Object {
     #[inline]
     fn lt(&self, other: &Object) -> bool {
-        match self.cmp(other) {
-            Ordering::Less  => true,
-            _               => false,
-        }
+        matches!(self.cmp(other), Ordering::Less)
     }
 
     fn partial_cmp(&self, other: &Object) -> Option<Ordering> {
@@ -81,15 +92,15 @@ impl Ord for Object {
     #[inline]
     fn cmp(&self, other: &Object) -> Ordering {
         if self.weight < other.weight {
-            return Ordering::Less;
+            Ordering::Less
         } else if self.weight > other.weight {
-            return Ordering::Greater;
+            Ordering::Greater
         } else if self.name < other.name {
-            return Ordering::Less;
+            Ordering::Less
         } else if self.name > other.name {
-            return Ordering::Greater;
+            Ordering::Greater
         } else {
-            return Ordering::Equal;
+            Ordering::Equal
         }
     }
 }
@@ -100,7 +111,7 @@ mod tests {
 
     #[test]
     fn test_template_method() {
-        let mut objects = vec![
+        let mut objects = [
             Object::new("object1", 3_f32),
             Object::new("object2", 2_f32),
             Object::new("object3", 1_f32),